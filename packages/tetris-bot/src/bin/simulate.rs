@@ -0,0 +1,132 @@
+//! Plays seeded games with a bot on every core and prints how they went
+//!
+//! cargo run --release -p tetris-bot --bin simulate -- --games 1000 --bot heuristic
+
+use std::env;
+use std::fs;
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Instant;
+
+use tetris_bot::{play, Bot, GameResult, HeuristicBot, RandomBot, Weights};
+
+struct Options {
+  games: u64,
+  threads: usize,
+  seed: u64, // game i is played with seed + i
+  bot: String,
+  weights: Weights, // used by the heuristic bot
+  max_pieces: u32,
+}
+
+fn usage() -> ! {
+  eprintln!("usage: simulate [--games N] [--threads N] [--seed N] [--bot heuristic|random] [--weights FILE] [--max-pieces N]");
+  process::exit(2);
+}
+
+fn parse_options() -> Options {
+  let mut options = Options {
+    games: 1000,
+    threads: thread::available_parallelism().map_or(1, |n| n.get()),
+    seed: 0,
+    bot: String::from("heuristic"),
+    weights: Weights::default(),
+    max_pieces: 1000,
+  };
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    let value = args.next().unwrap_or_else(|| usage());
+    let parsed = match arg.as_str() {
+      "--games" => value.parse().map(|n| options.games = n).is_ok(),
+      "--threads" => value.parse().map(|n| options.threads = n).is_ok(),
+      "--seed" => value.parse().map(|n| options.seed = n).is_ok(),
+      "--max-pieces" => value.parse().map(|n| options.max_pieces = n).is_ok(),
+      "--bot" => {
+        options.bot = value;
+        true
+      }
+      "--weights" => {
+        let text = fs::read_to_string(&value).unwrap_or_else(|err| {
+          eprintln!("could not read {}: {}", value, err);
+          process::exit(1);
+        });
+        options.weights = text.parse().unwrap_or_else(|err| {
+          eprintln!("could not parse {}: {}", value, err);
+          process::exit(1);
+        });
+        true
+      }
+      _ => false,
+    };
+    if !parsed {
+      usage();
+    }
+  }
+  options
+}
+
+fn make_bot(options: &Options, seed: u64) -> Box<dyn Bot> {
+  match options.bot.as_str() {
+    "heuristic" => Box::new(HeuristicBot::new(options.weights)),
+    "random" => Box::new(RandomBot::new(seed)),
+    _ => usage(),
+  }
+}
+
+/**
+ * Min, 10th percentile, median, 90th percentile, max and mean
+ */
+fn summary(mut values: Vec<f64>) -> [f64; 6] {
+  values.sort_by(f64::total_cmp);
+  let at = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize];
+  let mean = values.iter().sum::<f64>() / values.len() as f64;
+  [at(0.0), at(0.1), at(0.5), at(0.9), at(1.0), mean]
+}
+
+fn main() {
+  let options = parse_options();
+  if options.games == 0 || options.threads == 0 {
+    usage();
+  }
+  make_bot(&options, 0);
+
+  let start = Instant::now();
+  let next_game = AtomicU64::new(0);
+  let results: Mutex<Vec<GameResult>> = Mutex::new(Vec::with_capacity(options.games as usize));
+
+  thread::scope(|scope| {
+    for _ in 0..options.threads {
+      scope.spawn(|| loop {
+        let game = next_game.fetch_add(1, Ordering::Relaxed);
+        if game >= options.games {
+          break;
+        }
+        let seed = options.seed + game;
+        let result = play(make_bot(&options, seed).as_mut(), tetris_bot::game(seed), options.max_pieces);
+        results.lock().unwrap().push(result);
+      });
+    }
+  });
+
+  let results = results.into_inner().unwrap();
+  let topped_out = results.iter().filter(|result| result.topped_out).count();
+  println!(
+    "{} games with the {} bot on {} threads in {:.1}s, {} topped out before {} pieces",
+    results.len(), options.bot, options.threads, start.elapsed().as_secs_f64(), topped_out, options.max_pieces
+  );
+  println!("{:>8} {:>10} {:>10} {:>10} {:>10} {:>10} {:>10}", "", "min", "p10", "median", "p90", "max", "mean");
+
+  let rows: [(&str, Vec<f64>); 4] = [
+    ("lines", results.iter().map(|result| result.lines as f64).collect()),
+    ("score", results.iter().map(|result| result.score as f64).collect()),
+    ("pieces", results.iter().map(|result| result.pieces as f64).collect()),
+    ("pps", results.iter().map(GameResult::pps).collect()),
+  ];
+  for (name, values) in rows.iter() {
+    let [min, p10, median, p90, max, mean] = summary(values.clone());
+    println!("{:>8} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1} {:>10.1}", name, min, p10, median, p90, max, mean);
+  }
+}
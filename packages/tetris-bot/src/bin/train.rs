@@ -0,0 +1,69 @@
+//! Evolves the weights of the heuristic bot and writes the best ones to a file that `simulate --weights` can load
+//!
+//! cargo run --release -p tetris-bot --bin train -- --generations 20 --out weights.txt
+
+use std::env;
+use std::fs;
+use std::process;
+use std::thread;
+
+use tetris_bot::{fitness, TrainOptions, Trainer, Weights};
+
+fn usage() -> ! {
+  eprintln!("usage: train [--generations N] [--population N] [--games N] [--max-pieces N] [--threads N] [--seed N] [--out FILE]");
+  process::exit(2);
+}
+
+fn main() {
+  let mut options = TrainOptions {
+    threads: thread::available_parallelism().map_or(1, |n| n.get()),
+    ..TrainOptions::default()
+  };
+  let mut generations = 20;
+  let mut out = String::from("weights.txt");
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    let value = args.next().unwrap_or_else(|| usage());
+    let parsed = match arg.as_str() {
+      "--generations" => value.parse().map(|n| generations = n).is_ok(),
+      "--population" => value.parse().map(|n| options.population = n).is_ok(),
+      "--games" => value.parse().map(|n| options.games = n).is_ok(),
+      "--max-pieces" => value.parse().map(|n| options.max_pieces = n).is_ok(),
+      "--threads" => value.parse().map(|n| options.threads = n).is_ok(),
+      "--seed" => value.parse().map(|n| options.seed = n).is_ok(),
+      "--out" => {
+        out = value;
+        true
+      }
+      _ => false,
+    };
+    if !parsed || options.games == 0 {
+      usage();
+    }
+  }
+
+  let mut trainer = Trainer::new(options);
+  let mut best: Option<(Weights, f64)> = None;
+  for _ in 0..generations {
+    let (weights, score) = trainer.step();
+    println!("generation {:>3}: best {:.1} lines, {:?}", trainer.generation(), score, weights.0);
+    // scores of different generations come from different games, so the best ones are played again on the same seeds
+    best = match best {
+      Some((old, _)) => {
+        let seeds: Vec<u64> = (0..options.games as u64).map(|i| options.seed ^ (1 << 63) ^ i).collect();
+        let (old_score, new_score) = (fitness(&old, &seeds, options.max_pieces), fitness(&weights, &seeds, options.max_pieces));
+        if new_score > old_score { Some((weights, new_score)) } else { Some((old, old_score)) }
+      }
+      None => Some((weights, score)),
+    };
+  }
+
+  let (weights, _) = best.unwrap_or_else(|| usage());
+  let text = format!("# trained with seed {} over {} generations\n{}", options.seed, generations, weights);
+  if let Err(err) = fs::write(&out, text) {
+    eprintln!("could not write {}: {}", out, err);
+    process::exit(1);
+  }
+  println!("wrote {}", out);
+}
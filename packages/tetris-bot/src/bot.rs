@@ -0,0 +1,67 @@
+use tetris::{Pcg32, Tetris};
+
+use crate::{features, place, placements, Placement, Weights};
+
+/**
+ * Decides where each block goes
+ */
+pub trait Bot {
+  /**
+   * Returns None if the current block has nowhere to go
+   */
+  fn choose(&mut self, tetris: &Tetris) -> Option<Placement>;
+}
+
+/**
+ * Picks the placement with the best weighted board features, the first one wins ties
+ */
+pub struct HeuristicBot {
+  pub weights: Weights,
+}
+
+impl HeuristicBot {
+  pub fn new(weights: Weights) -> Self {
+    Self { weights }
+  }
+}
+
+impl Bot for HeuristicBot {
+  fn choose(&mut self, tetris: &Tetris) -> Option<Placement> {
+    let board = tetris.board();
+    let mut best: Option<(f64, Placement)> = None;
+    for placement in placements(tetris) {
+      let score = match place(&board, &placement.block) {
+        Some((board, lines)) => self.weights.evaluate(&features(&board, lines)),
+        None => f64::NEG_INFINITY, // locking above the board tops out
+      };
+      if best.as_ref().is_none_or(|(best, _)| score > *best) {
+        best = Some((score, placement));
+      }
+    }
+    best.map(|(_, placement)| placement)
+  }
+}
+
+/**
+ * Picks any placement, a baseline for the other bots
+ */
+pub struct RandomBot {
+  rng: Pcg32,
+}
+
+impl RandomBot {
+  pub fn new(seed: u64) -> Self {
+    Self { rng: Pcg32::new(seed) }
+  }
+}
+
+impl Bot for RandomBot {
+  fn choose(&mut self, tetris: &Tetris) -> Option<Placement> {
+    let mut placements = placements(tetris);
+    if placements.is_empty() {
+      return None;
+    }
+    let i = self.rng.next_u32() as usize % placements.len();
+    Some(placements.swap_remove(i))
+  }
+}
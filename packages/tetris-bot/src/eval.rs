@@ -0,0 +1,185 @@
+use std::fmt;
+use std::str::FromStr;
+
+use tetris::{Block, Board};
+
+/**
+ * Names of the board features, in the order of the weights
+ */
+pub const FEATURES: [&str; 7] = ["lines", "holes", "height", "bumpiness", "wells", "row_transitions", "column_transitions"];
+
+/**
+ * How much each feature of the board counts when choosing a placement, higher scores are better
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Weights(pub [f64; 7]);
+
+impl Default for Weights {
+  fn default() -> Self {
+    Self([0.76, -0.36, -0.51, -0.18, -0.05, -0.1, -0.1])
+  }
+}
+
+impl Weights {
+  pub fn evaluate(&self, features: &[f64; 7]) -> f64 {
+    self.0.iter().zip(features.iter()).map(|(w, f)| w * f).sum()
+  }
+
+  /**
+   * Scales the weights to length 1, only the direction matters when comparing placements
+   */
+  pub fn normalized(&self) -> Self {
+    let length = self.0.iter().map(|w| w * w).sum::<f64>().sqrt();
+    if length == 0.0 {
+      return *self;
+    }
+    let mut weights = self.0;
+    weights.iter_mut().for_each(|w| *w /= length);
+    Self(weights)
+  }
+}
+
+/**
+ * Weights files have one feature per line, the name and the weight separated by whitespace, lines starting with '#' are comments
+ */
+impl fmt::Display for Weights {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    for (name, weight) in FEATURES.iter().zip(self.0.iter()) {
+      writeln!(f, "{} {}", name, weight)?;
+    }
+    Ok(())
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseWeightsError {
+  UnknownFeature(String),
+  MissingFeature(&'static str),
+  InvalidLine(usize), // line number that is not a name and a number
+}
+
+impl fmt::Display for ParseWeightsError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::UnknownFeature(name) => write!(f, "unknown feature '{}'", name),
+      Self::MissingFeature(name) => write!(f, "no weight for '{}'", name),
+      Self::InvalidLine(line) => write!(f, "line {} is not a feature name and a weight", line),
+    }
+  }
+}
+
+impl std::error::Error for ParseWeightsError {}
+
+impl FromStr for Weights {
+  type Err = ParseWeightsError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut weights = [None; 7];
+    for (i, line) in s.lines().enumerate() {
+      let line = line.trim();
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let mut parts = line.split_whitespace();
+      let (name, weight) = match (parts.next(), parts.next().map(str::parse::<f64>), parts.next()) {
+        (Some(name), Some(Ok(weight)), None) => (name, weight),
+        _ => return Err(ParseWeightsError::InvalidLine(i + 1)),
+      };
+      let index = FEATURES.iter().position(|&feature| feature == name).ok_or_else(|| ParseWeightsError::UnknownFeature(name.to_string()))?;
+      weights[index] = Some(weight);
+    }
+
+    let mut values = [0.0; 7];
+    for (i, weight) in weights.iter().enumerate() {
+      values[i] = weight.ok_or(ParseWeightsError::MissingFeature(FEATURES[i]))?;
+    }
+    Ok(Self(values))
+  }
+}
+
+/**
+ * Locks the block into a copy of the board and clears full rows,
+ * returns None if the block does not fit or would lock above the board
+ */
+pub fn place(board: &Board, block: &Block) -> Option<(Board, u32)> {
+  let mut board = board.clone();
+  for &(x, y) in block.cells().iter() {
+    if x < 0 || y < 0 || x >= board.width as isize || y >= board.height as isize || board.get(x as usize, y as usize) != 0 {
+      return None;
+    }
+    board.set(x as usize, y as usize, block.block_type as u32);
+  }
+
+  let rows: Vec<&[u32]> = board.cells.chunks_exact(board.width).filter(|row| row.contains(&0)).collect();
+  let lines = board.height - rows.len();
+  let mut cells = vec![0; lines * board.width];
+  rows.iter().for_each(|row| cells.extend_from_slice(row));
+  board.cells = cells;
+
+  Some((board, lines as u32))
+}
+
+/**
+ * Features of the board after a placement, see `FEATURES`
+ */
+pub fn features(board: &Board, lines: u32) -> [f64; 7] {
+  let (width, height) = (board.width, board.height);
+  let filled = |x: usize, y: usize| board.get(x, y) != 0;
+
+  let heights: Vec<usize> = (0..width)
+    .map(|x| (0..height).find(|&y| filled(x, y)).map_or(0, |y| height - y))
+    .collect();
+
+  let holes = (0..width)
+    .map(|x| (height - heights[x]..height).filter(|&y| !filled(x, y)).count())
+    .sum::<usize>();
+
+  let bumpiness = heights.windows(2).map(|pair| (pair[0] as isize - pair[1] as isize).abs()).sum::<isize>();
+
+  // walls are as high as the board
+  let wells = (0..width)
+    .map(|x| {
+      let left = if x == 0 { height } else { heights[x - 1] };
+      let right = if x + 1 == width { height } else { heights[x + 1] };
+      left.min(right).saturating_sub(heights[x])
+    })
+    .sum::<usize>();
+
+  // walls and the floor count as filled
+  let row_transitions = (0..height)
+    .map(|y| {
+      let row: Vec<bool> = std::iter::once(true).chain((0..width).map(|x| filled(x, y))).chain(std::iter::once(true)).collect();
+      row.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    })
+    .sum::<usize>();
+
+  let column_transitions = (0..width)
+    .map(|x| {
+      let column: Vec<bool> = (0..height).map(|y| filled(x, y)).chain(std::iter::once(true)).collect();
+      column.windows(2).filter(|pair| pair[0] != pair[1]).count()
+    })
+    .sum::<usize>();
+
+  [
+    lines as f64,
+    holes as f64,
+    heights.iter().sum::<usize>() as f64,
+    bumpiness as f64,
+    wells as f64,
+    row_transitions as f64,
+    column_transitions as f64,
+  ]
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn weights_round_trip_through_text() {
+    let weights = Weights([1.5, -2.0, 0.25, 0.0, -0.125, 3.0, -1.0]);
+    assert_eq!(weights.to_string().parse::<Weights>(), Ok(weights));
+    assert_eq!("# comment\nlines 1".parse::<Weights>(), Err(ParseWeightsError::MissingFeature("holes")));
+    assert_eq!("height one".parse::<Weights>(), Err(ParseWeightsError::InvalidLine(1)));
+  }
+}
@@ -0,0 +1,11 @@
+mod bot;
+mod eval;
+mod placement;
+mod sim;
+mod train;
+
+pub use bot::{Bot, HeuristicBot, RandomBot};
+pub use eval::{features, place, ParseWeightsError, Weights, FEATURES};
+pub use placement::{placements, Placement};
+pub use sim::{play, GameResult};
+pub use train::{fitness, game, TrainOptions, Trainer};
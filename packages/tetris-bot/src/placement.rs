@@ -0,0 +1,129 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use tetris::{Block, MoveDirection, RotationDirection, Tetris, TetrisAction};
+
+/**
+ * A spot where the current block can lock, with the actions that take it there from where it is now
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Placement {
+  pub block: Block,
+  pub actions: Vec<TetrisAction>, // always ends with a hard drop
+}
+
+const ACTIONS: [TetrisAction; 5] = [
+  TetrisAction::Move(MoveDirection::Left),
+  TetrisAction::Move(MoveDirection::Right),
+  TetrisAction::Rotate(RotationDirection::Clockwise),
+  TetrisAction::Rotate(RotationDirection::CounterClockwise),
+  TetrisAction::Move(MoveDirection::Down),
+];
+
+// blocks with the same origin and rotation state cover the same cells and rotate the same way
+fn key(block: &Block) -> (isize, isize, usize) {
+  (block.origin.0, block.origin.1, block.rot_state as usize)
+}
+
+fn sorted_cells(block: &Block) -> [(isize, isize); 4] {
+  let mut cells = block.cells();
+  cells.sort_unstable();
+  cells
+}
+
+/**
+ * Every spot the current block can reach and lock in, including tucks and spins.
+ * Blocks are searched breadth first so each spot comes with the fewest actions,
+ * spots that cover the same cells in a different rotation state are only listed once
+ */
+pub fn placements(tetris: &Tetris) -> Vec<Placement> {
+  let start = tetris.curr_block;
+  let mut parents: HashMap<(isize, isize, usize), (Block, TetrisAction)> = HashMap::new();
+  let mut seen = HashSet::new();
+  let mut covered = HashSet::new();
+  let mut queue = VecDeque::new();
+  let mut placements = Vec::new();
+
+  seen.insert(key(&start));
+  queue.push_back(start);
+
+  while let Some(block) = queue.pop_front() {
+    if tetris.try_action(&block, TetrisAction::Move(MoveDirection::Down)).is_none() && covered.insert(sorted_cells(&block)) {
+      placements.push(Placement {
+        block,
+        actions: path(&parents, &start, &block),
+      });
+    }
+
+    for &action in ACTIONS.iter() {
+      if let Some(next) = tetris.try_action(&block, action) {
+        if seen.insert(key(&next)) {
+          parents.insert(key(&next), (block, action));
+          queue.push_back(next);
+        }
+      }
+    }
+  }
+
+  placements
+}
+
+// walks back from the placement to the start, the drops at the end are left to the hard drop
+fn path(parents: &HashMap<(isize, isize, usize), (Block, TetrisAction)>, start: &Block, block: &Block) -> Vec<TetrisAction> {
+  let mut actions = Vec::new();
+  let mut block = *block;
+  while key(&block) != key(start) {
+    let (parent, action) = parents[&key(&block)];
+    actions.push(action);
+    block = parent;
+  }
+  actions.reverse();
+
+  while actions.last() == Some(&TetrisAction::Move(MoveDirection::Down)) {
+    actions.pop();
+  }
+  actions.push(TetrisAction::HardDrop);
+  actions
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tetris::{BlockType, PieceGenerator, Randomizer, TetrisBuilder};
+
+  struct Only(BlockType);
+
+  impl PieceGenerator for Only {
+    fn next(&mut self, _: &mut dyn Randomizer<u32>) -> BlockType {
+      self.0
+    }
+  }
+
+  #[test]
+  fn finds_every_column_on_an_empty_board() {
+    let tetris = TetrisBuilder::new(10, 20).with_piece_generator(Box::new(Only(BlockType::O))).build();
+    let placements = placements(&tetris);
+    assert_eq!(placements.len(), 9);
+    assert!(placements.iter().all(|placement| placement.actions.last() == Some(&TetrisAction::HardDrop)));
+    assert!(placements.iter().all(|placement| !placement.actions.contains(&TetrisAction::Move(MoveDirection::Down))));
+  }
+
+  #[test]
+  fn finds_tucks_under_overhangs() {
+    let board = "
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxZZZZ
+      xxxxxxxxxx
+      xxxxxxxxxx
+    ";
+    let tetris = TetrisBuilder::new(0, 0)
+      .with_board(board.parse().unwrap())
+      .with_piece_generator(Box::new(Only(BlockType::O)))
+      .build();
+    let tuck = placements(&tetris)
+      .into_iter()
+      .find(|placement| sorted_cells(&placement.block) == [(6, 4), (6, 5), (7, 4), (7, 5)]);
+    assert!(tuck.unwrap().actions.contains(&TetrisAction::Move(MoveDirection::Down)));
+  }
+}
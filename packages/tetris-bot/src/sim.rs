@@ -0,0 +1,106 @@
+use std::time::{Duration, Instant};
+
+use tetris::TetrisBuilder;
+
+use crate::Bot;
+
+/**
+ * How a game played by a bot went
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GameResult {
+  pub pieces: u32,
+  pub lines: u32,
+  pub score: u64,
+  pub topped_out: bool, // false if the game was stopped at the piece limit or the mode was finished
+  pub duration: Duration, // time it took to play, bot included
+}
+
+impl GameResult {
+  /**
+   * Pieces per second of real time, how fast the bot and engine run together
+   */
+  pub fn pps(&self) -> f64 {
+    let seconds = self.duration.as_secs_f64();
+    if seconds > 0.0 { self.pieces as f64 / seconds } else { 0.0 }
+  }
+}
+
+/**
+ * Lets the bot play the game until it is over or has placed `max_pieces` blocks, the delays between blocks are ticked through
+ */
+pub fn play(bot: &mut dyn Bot, builder: TetrisBuilder, max_pieces: u32) -> GameResult {
+  let start = Instant::now();
+  let mut tetris = builder.build();
+
+  while !tetris.phase().is_over() && tetris.stats().pieces < max_pieces {
+    let placement = match bot.choose(&tetris) {
+      Some(placement) => placement,
+      None => break,
+    };
+    for action in placement.actions {
+      tetris.do_action(action);
+    }
+    // the line clear and entry delays pass before the next block can be moved
+    while !tetris.phase().is_over() && !tetris.phase().has_block() {
+      tetris.tick();
+    }
+  }
+
+  GameResult {
+    pieces: tetris.stats().pieces,
+    lines: tetris.lines(),
+    score: tetris.score(),
+    topped_out: tetris.is_game_over(),
+    duration: start.elapsed(),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{HeuristicBot, RandomBot, Weights};
+  use tetris::{Dig, SevenBag, SpeedTable};
+
+  fn builder(seed: u64) -> TetrisBuilder {
+    TetrisBuilder::new(10, 20).with_seed(seed).with_piece_generator(Box::new(SevenBag::new()))
+  }
+
+  #[test]
+  fn heuristic_bot_outlasts_random_bot() {
+    let heuristic = play(&mut HeuristicBot::new(Weights::default()), builder(3), 200);
+    let random = play(&mut RandomBot::new(3), builder(3), 200);
+
+    assert_eq!(heuristic.pieces, 200);
+    assert!(!heuristic.topped_out);
+    assert!(heuristic.lines >= 60);
+    assert!(random.topped_out);
+  }
+
+  #[test]
+  fn plays_mirrored_games_as_well() {
+    let mirrored = play(&mut HeuristicBot::new(Weights::default()), builder(3).with_mirror(), 200);
+    assert_eq!(mirrored.pieces, 200);
+    assert!(!mirrored.topped_out);
+  }
+
+  #[test]
+  fn games_are_reproducible() {
+    let first = play(&mut HeuristicBot::new(Weights::default()), builder(9), 50);
+    let second = play(&mut HeuristicBot::new(Weights::default()), builder(9), 50);
+    assert_eq!((first.lines, first.score), (second.lines, second.score));
+  }
+
+  #[test]
+  fn waits_out_the_delays() {
+    let result = play(&mut HeuristicBot::new(Weights::default()), builder(5).with_speed_table(SpeedTable::nes()), 30);
+    assert_eq!(result.pieces, 30);
+  }
+
+  #[test]
+  fn stops_when_the_dig_is_finished() {
+    let result = play(&mut HeuristicBot::new(Weights::default()), builder(5).with_dig(Dig::new(2)), 200);
+    assert!(!result.topped_out);
+    assert!(result.pieces < 200);
+  }
+}
@@ -0,0 +1,177 @@
+use std::thread;
+
+use tetris::{Pcg32, SevenBag, Srs, TetrisBuilder};
+
+use crate::{play, HeuristicBot, Weights};
+
+/**
+ * Settings of a training run, runs with the same settings give the same weights
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct TrainOptions {
+  pub seed: u64,
+  pub population: usize,
+  pub games: usize, // games every candidate plays per generation, at least one, all candidates play the same seeds
+  pub max_pieces: u32,
+  pub threads: usize, // only changes how fast the run is, not its result
+}
+
+impl Default for TrainOptions {
+  fn default() -> Self {
+    Self {
+      seed: 0,
+      population: 100,
+      games: 5,
+      max_pieces: 500,
+      threads: 1,
+    }
+  }
+}
+
+/**
+ * The game every candidate is measured with
+ */
+pub fn game(seed: u64) -> TetrisBuilder {
+  TetrisBuilder::new(10, 20)
+    .with_seed(seed)
+    .with_rotation_system(Box::new(Srs))
+    .with_piece_generator(Box::new(SevenBag::new()))
+}
+
+/**
+ * Mean lines cleared over the games with the given seeds
+ */
+pub fn fitness(weights: &Weights, seeds: &[u64], max_pieces: u32) -> f64 {
+  let lines: u32 = seeds
+    .iter()
+    .map(|&seed| play(&mut HeuristicBot::new(*weights), game(seed), max_pieces).lines)
+    .sum();
+  lines as f64 / seeds.len() as f64
+}
+
+/**
+ * Genetic algorithm over normalized weight vectors.
+ * Each generation the candidates play the same games, the weakest 30% are replaced by children of tournament winners,
+ * a child is the average of its parents weighted by their fitness, sometimes with one weight nudged
+ */
+pub struct Trainer {
+  options: TrainOptions,
+  rng: Pcg32,
+  population: Vec<Weights>,
+  generation: u32,
+}
+
+impl Trainer {
+  pub fn new(options: TrainOptions) -> Self {
+    let mut rng = Pcg32::new(options.seed);
+    let population = (0..options.population.max(2))
+      .map(|_| {
+        let mut weights = [0.0; 7];
+        weights.iter_mut().for_each(|w| *w = uniform(&mut rng) * 2.0 - 1.0);
+        Weights(weights).normalized()
+      })
+      .collect();
+
+    Self {
+      options,
+      rng,
+      population,
+      generation: 0,
+    }
+  }
+
+  pub fn generation(&self) -> u32 {
+    self.generation
+  }
+
+  /**
+   * Plays one generation and breeds the next, returns the best candidate of the generation and its fitness
+   */
+  pub fn step(&mut self) -> (Weights, f64) {
+    let seeds: Vec<u64> = (0..self.options.games.max(1)).map(|_| u64::from(self.rng.next_u32()) << 32 | u64::from(self.rng.next_u32())).collect();
+    let scores = self.evaluate(&seeds);
+
+    let mut ranked: Vec<(Weights, f64)> = self.population.iter().copied().zip(scores).collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let best = ranked[0];
+
+    let children = (ranked.len() * 3 / 10).max(1);
+    let tournament = (ranked.len() / 10).max(2);
+    let offspring: Vec<Weights> = (0..children).map(|_| self.breed(&ranked, tournament)).collect();
+
+    ranked.truncate(ranked.len() - children);
+    self.population = ranked.into_iter().map(|(weights, _)| weights).chain(offspring).collect();
+    self.generation += 1;
+    best
+  }
+
+  // scores are stored by index, so the threads do not change the result
+  fn evaluate(&self, seeds: &[u64]) -> Vec<f64> {
+    let threads = self.options.threads.max(1);
+    let chunk = self.population.len().div_ceil(threads);
+    let max_pieces = self.options.max_pieces;
+    thread::scope(|scope| {
+      let handles: Vec<_> = self
+        .population
+        .chunks(chunk)
+        .map(|candidates| scope.spawn(move || candidates.iter().map(|weights| fitness(weights, seeds, max_pieces)).collect::<Vec<_>>()))
+        .collect();
+      handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+  }
+
+  fn breed(&mut self, ranked: &[(Weights, f64)], tournament: usize) -> Weights {
+    // the two best of a random sample of different candidates are the parents, drawn with a partial shuffle
+    let mut sample: Vec<usize> = (0..ranked.len()).collect();
+    for i in 0..tournament {
+      let j = i + self.rng.next_u32() as usize % (ranked.len() - i);
+      sample.swap(i, j);
+    }
+    sample.truncate(tournament);
+    sample.sort_unstable();
+    let (a, b) = (&ranked[sample[0]], &ranked[sample[1]]);
+
+    let total = a.1 + b.1;
+    let (w_a, w_b) = if total > 0.0 { (a.1 / total, b.1 / total) } else { (0.5, 0.5) };
+    let mut child = [0.0; 7];
+    for (i, w) in child.iter_mut().enumerate() {
+      *w = (a.0).0[i] * w_a + (b.0).0[i] * w_b;
+    }
+
+    if uniform(&mut self.rng) < 0.05 {
+      let i = self.rng.next_u32() as usize % child.len();
+      child[i] += uniform(&mut self.rng) * 0.4 - 0.2;
+    }
+    Weights(child).normalized()
+  }
+}
+
+// a number in [0, 1)
+fn uniform(rng: &mut Pcg32) -> f64 {
+  rng.next_u32() as f64 / (u32::MAX as f64 + 1.0)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn training_is_reproducible() {
+    let options = TrainOptions {
+      seed: 5,
+      population: 6,
+      games: 1,
+      max_pieces: 20,
+      threads: 1,
+    };
+    let run = |threads| {
+      let mut trainer = Trainer::new(TrainOptions { threads, ..options });
+      (0..2).map(|_| trainer.step()).last().unwrap()
+    };
+    assert_eq!(run(1), run(3));
+
+    // without games every candidate would score NaN
+    let mut trainer = Trainer::new(TrainOptions { games: 0, ..options });
+    assert!(!trainer.step().1.is_nan());
+  }
+}
@@ -0,0 +1,13 @@
+use std::env;
+use std::path::Path;
+
+// generates tetris.h from the exported functions, the copy in include/ is checked against it by the tests
+fn main() {
+  println!("cargo:rerun-if-changed=src/lib.rs");
+  println!("cargo:rerun-if-changed=cbindgen.toml");
+  let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+  let out_dir = env::var("OUT_DIR").unwrap();
+  cbindgen::generate(&crate_dir)
+    .expect("failed to generate the C header")
+    .write_to_file(Path::new(&out_dir).join("tetris.h"));
+}
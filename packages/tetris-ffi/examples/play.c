@@ -0,0 +1,57 @@
+/*
+ * Drops blocks straight down until the game is over and prints the last frame.
+ *
+ * From the root of the repository:
+ *   cargo build -p tetris-ffi
+ *   cc packages/tetris-ffi/examples/play.c -Ipackages/tetris-ffi/include target/debug/libtetris_ffi.a -lpthread -ldl -lm -o play
+ *   ./play
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+
+#include "tetris.h"
+
+static char cell_char(uint32_t cell) {
+  static const char letters[] = "xIJLOSTZG";
+  switch (cell >> 8) {
+    case TETRIS_CELL_EMPTY:
+      return ' ';
+    case TETRIS_CELL_GHOST:
+      return '.';
+    default:
+      return letters[(cell & 0xff) % (sizeof(letters) - 1)];
+  }
+}
+
+int main(void) {
+  Tetris *tetris = tetris_create(10, 20, 42);
+  if (tetris == NULL) {
+    return 1;
+  }
+
+  int moves = 0;
+  while (!tetris_is_game_over(tetris)) {
+    /* spread the blocks out a little */
+    tetris_apply_action(tetris, moves++ % 2 ? TETRIS_ACTION_LEFT : TETRIS_ACTION_RIGHT);
+    tetris_tick(tetris);
+    tetris_update(tetris);
+  }
+
+  size_t len = tetris_frame(tetris, NULL, 0);
+  uint32_t *cells = malloc(len * sizeof(uint32_t));
+  tetris_frame(tetris, cells, len);
+
+  uint32_t width = tetris_width(tetris);
+  for (size_t i = 0; i < len; i++) {
+    putchar(cell_char(cells[i]));
+    if ((i + 1) % width == 0) {
+      putchar('\n');
+    }
+  }
+  printf("score %llu, lines %u\n", (unsigned long long)tetris_score(tetris), tetris_lines(tetris));
+
+  free(cells);
+  tetris_destroy(tetris);
+  return 0;
+}
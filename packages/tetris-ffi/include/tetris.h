@@ -0,0 +1,159 @@
+#ifndef TETRIS_H
+#define TETRIS_H
+
+/* Generated by cbindgen from packages/tetris-ffi, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef struct Tetris Tetris;
+
+#define TETRIS_ACTION_DOWN 0
+
+#define TETRIS_ACTION_LEFT 1
+
+#define TETRIS_ACTION_RIGHT 2
+
+#define TETRIS_ACTION_ROTATE_CCW 3
+
+#define TETRIS_ACTION_ROTATE_CW 4
+
+#define TETRIS_ACTION_HOLD 5
+
+#define TETRIS_ACTION_HARD_DROP 6
+
+#define TETRIS_CELL_EMPTY 0
+
+#define TETRIS_CELL_LOCKED 1
+
+#define TETRIS_CELL_ACTIVE 2
+
+#define TETRIS_CELL_GHOST 3
+
+#define TETRIS_PHASE_FALLING 0
+
+#define TETRIS_PHASE_LOCKING 1
+
+#define TETRIS_PHASE_LINE_CLEAR 2
+
+#define TETRIS_PHASE_ENTRY 3
+
+#define TETRIS_PHASE_GAME_OVER 4
+
+#define TETRIS_PHASE_FINISHED 5
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ * Creates a game, the same seed and size always deal the same blocks.
+ * The game must be destroyed with `tetris_destroy`. Engine events are not passed on, they are dropped after every call
+ */
+Tetris *tetris_create(uint32_t width,
+                      uint32_t height,
+                      uint64_t seed);
+
+/*
+ * # Safety
+ * `tetris` must come from `tetris_create` and must not be used afterwards, null is ignored
+ */
+void tetris_destroy(Tetris *tetris);
+
+/*
+ * Advances the game clock by one frame
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+void tetris_tick(Tetris *tetris);
+
+/*
+ * Moves the current block down one row, locking it if it can not move
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+void tetris_update(Tetris *tetris);
+
+/*
+ * Applies one of the `TETRIS_ACTION_` ids, returns false if the id is unknown
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+bool tetris_apply_action(Tetris *tetris, uint32_t action_id);
+
+/*
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+void tetris_restart(Tetris *tetris);
+
+/*
+ * Writes the visible cells row by row from the top into `cells` and returns the number of cells in a frame,
+ * see the `TETRIS_CELL_` kinds. Nothing is written if `len` is smaller than that, so it can be called with a null buffer to get the size
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`, `cells` must be valid for `len` writes
+ */
+size_t tetris_frame(const Tetris *tetris,
+                    uint32_t *cells,
+                    size_t len);
+
+/*
+ * Width of a frame in cells
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+uint32_t tetris_width(const Tetris *tetris);
+
+/*
+ * Height of a frame in cells
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+uint32_t tetris_height(const Tetris *tetris);
+
+/*
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+uint64_t tetris_score(const Tetris *tetris);
+
+/*
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+uint32_t tetris_lines(const Tetris *tetris);
+
+/*
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+uint32_t tetris_level(const Tetris *tetris);
+
+/*
+ * One of the `TETRIS_PHASE_` ids, a null game counts as over
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+uint32_t tetris_phase(const Tetris *tetris);
+
+/*
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+bool tetris_is_game_over(const Tetris *tetris);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* TETRIS_H */
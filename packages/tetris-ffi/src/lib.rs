@@ -0,0 +1,277 @@
+//! C bindings for the tetris engine, see `include/tetris.h` for the C side
+
+use std::ptr;
+
+use tetris::{CellKind, MoveDirection, Phase, RotationDirection, Tetris, TetrisAction, TetrisBuilder};
+
+// action ids, the same as the input ids of the web version
+pub const TETRIS_ACTION_DOWN: u32 = 0;
+pub const TETRIS_ACTION_LEFT: u32 = 1;
+pub const TETRIS_ACTION_RIGHT: u32 = 2;
+pub const TETRIS_ACTION_ROTATE_CCW: u32 = 3;
+pub const TETRIS_ACTION_ROTATE_CW: u32 = 4;
+pub const TETRIS_ACTION_HOLD: u32 = 5;
+pub const TETRIS_ACTION_HARD_DROP: u32 = 6;
+
+// frame cells hold the playfield value in the low byte and one of these kinds in the byte above it
+pub const TETRIS_CELL_EMPTY: u32 = 0;
+pub const TETRIS_CELL_LOCKED: u32 = 1;
+pub const TETRIS_CELL_ACTIVE: u32 = 2;
+pub const TETRIS_CELL_GHOST: u32 = 3;
+
+// phases of `tetris_phase`, see `Phase`
+pub const TETRIS_PHASE_FALLING: u32 = 0;
+pub const TETRIS_PHASE_LOCKING: u32 = 1;
+pub const TETRIS_PHASE_LINE_CLEAR: u32 = 2;
+pub const TETRIS_PHASE_ENTRY: u32 = 3;
+pub const TETRIS_PHASE_GAME_OVER: u32 = 4;
+pub const TETRIS_PHASE_FINISHED: u32 = 5;
+
+fn action(id: u32) -> Option<TetrisAction> {
+  use MoveDirection::*;
+  use RotationDirection::*;
+  use TetrisAction::*;
+
+  match id {
+    TETRIS_ACTION_DOWN => Some(Move(Down)),
+    TETRIS_ACTION_LEFT => Some(Move(Left)),
+    TETRIS_ACTION_RIGHT => Some(Move(Right)),
+    TETRIS_ACTION_ROTATE_CCW => Some(Rotate(CounterClockwise)),
+    TETRIS_ACTION_ROTATE_CW => Some(Rotate(Clockwise)),
+    TETRIS_ACTION_HOLD => Some(Hold),
+    TETRIS_ACTION_HARD_DROP => Some(HardDrop),
+    _ => None,
+  }
+}
+
+fn encode_cell(kind: CellKind, value: u32) -> u32 {
+  let kind = match kind {
+    CellKind::Empty => TETRIS_CELL_EMPTY,
+    CellKind::Locked => TETRIS_CELL_LOCKED,
+    CellKind::Active => TETRIS_CELL_ACTIVE,
+    CellKind::Ghost => TETRIS_CELL_GHOST,
+  };
+  (kind << 8) | (value & 0xff)
+}
+
+/**
+ * Creates a game, the same seed and size always deal the same blocks.
+ * The game must be destroyed with `tetris_destroy`. Engine events are not passed on, they are dropped after every call
+ */
+#[no_mangle]
+pub extern "C" fn tetris_create(width: u32, height: u32, seed: u64) -> *mut Tetris {
+  if width < 4 || height == 0 {
+    return ptr::null_mut();
+  }
+  let tetris = TetrisBuilder::new(width as usize, height as usize).with_seed(seed).build();
+  Box::into_raw(Box::new(tetris))
+}
+
+/**
+ * # Safety
+ * `tetris` must come from `tetris_create` and must not be used afterwards, null is ignored
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_destroy(tetris: *mut Tetris) {
+  if !tetris.is_null() {
+    drop(Box::from_raw(tetris));
+  }
+}
+
+/**
+ * Advances the game clock by one frame
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_tick(tetris: *mut Tetris) {
+  if let Some(tetris) = tetris.as_mut() {
+    tetris.tick();
+    tetris.drain_events();
+  }
+}
+
+/**
+ * Moves the current block down one row, locking it if it can not move
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_update(tetris: *mut Tetris) {
+  if let Some(tetris) = tetris.as_mut() {
+    tetris.update();
+    tetris.drain_events();
+  }
+}
+
+/**
+ * Applies one of the `TETRIS_ACTION_` ids, returns false if the id is unknown
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_apply_action(tetris: *mut Tetris, action_id: u32) -> bool {
+  match (tetris.as_mut(), action(action_id)) {
+    (Some(tetris), Some(action)) => {
+      tetris.do_action(action);
+      tetris.drain_events();
+      true
+    }
+    _ => false,
+  }
+}
+
+/**
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_restart(tetris: *mut Tetris) {
+  if let Some(tetris) = tetris.as_mut() {
+    tetris.restart();
+    tetris.drain_events();
+  }
+}
+
+/**
+ * Writes the visible cells row by row from the top into `cells` and returns the number of cells in a frame,
+ * see the `TETRIS_CELL_` kinds. Nothing is written if `len` is smaller than that, so it can be called with a null buffer to get the size
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`, `cells` must be valid for `len` writes
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_frame(tetris: *const Tetris, cells: *mut u32, len: usize) -> usize {
+  let tetris = match tetris.as_ref() {
+    Some(tetris) => tetris,
+    None => return 0,
+  };
+  let frame = tetris.frame();
+  if !cells.is_null() && len >= frame.cells.len() {
+    let out = std::slice::from_raw_parts_mut(cells, frame.cells.len());
+    for (out, cell) in out.iter_mut().zip(frame.cells.iter()) {
+      *out = encode_cell(cell.kind, cell.value);
+    }
+  }
+  frame.cells.len()
+}
+
+/**
+ * Width of a frame in cells
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_width(tetris: *const Tetris) -> u32 {
+  tetris.as_ref().map_or(0, |tetris| tetris.frame_size().0 as u32)
+}
+
+/**
+ * Height of a frame in cells
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_height(tetris: *const Tetris) -> u32 {
+  tetris.as_ref().map_or(0, |tetris| tetris.frame_size().1 as u32)
+}
+
+/**
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_score(tetris: *const Tetris) -> u64 {
+  tetris.as_ref().map_or(0, Tetris::score)
+}
+
+/**
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_lines(tetris: *const Tetris) -> u32 {
+  tetris.as_ref().map_or(0, Tetris::lines)
+}
+
+/**
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_level(tetris: *const Tetris) -> u32 {
+  tetris.as_ref().map_or(0, Tetris::level)
+}
+
+/**
+ * One of the `TETRIS_PHASE_` ids, a null game counts as over
+ *
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_phase(tetris: *const Tetris) -> u32 {
+  match tetris.as_ref().map_or(Phase::GameOver, Tetris::phase) {
+    Phase::Falling => TETRIS_PHASE_FALLING,
+    Phase::Locking => TETRIS_PHASE_LOCKING,
+    Phase::LineClearAnimating => TETRIS_PHASE_LINE_CLEAR,
+    Phase::Entry => TETRIS_PHASE_ENTRY,
+    Phase::GameOver => TETRIS_PHASE_GAME_OVER,
+    Phase::Finished => TETRIS_PHASE_FINISHED,
+  }
+}
+
+/**
+ * # Safety
+ * `tetris` must be null or a live game from `tetris_create`
+ */
+#[no_mangle]
+pub unsafe extern "C" fn tetris_is_game_over(tetris: *const Tetris) -> bool {
+  tetris.as_ref().is_none_or(Tetris::is_game_over)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn plays_through_the_c_api() {
+    unsafe {
+      let tetris = tetris_create(10, 20, 1);
+      assert_eq!(tetris_frame(tetris, ptr::null_mut(), 0), 200);
+
+      assert!(tetris_apply_action(tetris, TETRIS_ACTION_RIGHT));
+      assert!(!tetris_apply_action(tetris, 42));
+      while !tetris_is_game_over(tetris) {
+        tetris_update(tetris);
+      }
+      // the events of the game were not kept around
+      assert!((*tetris).drain_events().is_empty());
+
+      let mut cells = vec![0; 200];
+      assert_eq!(tetris_frame(tetris, cells.as_mut_ptr(), cells.len()), 200);
+      assert!(cells.iter().any(|&cell| cell >> 8 == TETRIS_CELL_LOCKED));
+
+      assert_eq!(tetris_phase(tetris), TETRIS_PHASE_GAME_OVER);
+
+      tetris_restart(tetris);
+      assert!(!tetris_is_game_over(tetris));
+      assert_eq!(tetris_phase(tetris), TETRIS_PHASE_FALLING);
+      assert_eq!(tetris_score(tetris), 0);
+      tetris_destroy(tetris);
+    }
+  }
+
+  #[test]
+  fn header_is_up_to_date() {
+    // copy the generated header over include/tetris.h after changing the bindings
+    let generated = include_str!(concat!(env!("OUT_DIR"), "/tetris.h"));
+    assert_eq!(include_str!("../include/tetris.h"), generated);
+  }
+
+}
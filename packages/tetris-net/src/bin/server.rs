@@ -0,0 +1,69 @@
+//! Pairs players as they connect and runs their games, so both can play versus on the local network
+//!
+//! cargo run --release -p tetris-net --bin server -- --addr 0.0.0.0:7777
+
+use std::env;
+use std::net::TcpListener;
+use std::process;
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tetris_net::serve;
+
+fn usage() -> ! {
+  eprintln!("usage: server [--addr HOST:PORT] [--seed N]");
+  process::exit(2);
+}
+
+fn main() {
+  let mut addr = String::from("127.0.0.1:7777");
+  // a different game every time unless a seed is given, game i is played with seed + i
+  let mut seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
+
+  let mut args = env::args().skip(1);
+  while let Some(arg) = args.next() {
+    let value = args.next().unwrap_or_else(|| usage());
+    let parsed = match arg.as_str() {
+      "--addr" => {
+        addr = value;
+        true
+      }
+      "--seed" => value.parse().map(|n| seed = n).is_ok(),
+      _ => false,
+    };
+    if !parsed {
+      usage();
+    }
+  }
+
+  let listener = TcpListener::bind(&addr).unwrap_or_else(|err| {
+    eprintln!("could not listen on {}: {}", addr, err);
+    process::exit(1);
+  });
+  println!("listening on {}", addr);
+
+  let mut waiting = None;
+  for stream in listener.incoming() {
+    let stream = match stream {
+      Ok(stream) => stream,
+      Err(err) => {
+        eprintln!("could not accept a player: {}", err);
+        continue;
+      }
+    };
+    let first = match waiting.take() {
+      Some(first) => first,
+      None => {
+        waiting = Some(stream);
+        continue;
+      }
+    };
+
+    let game_seed = seed;
+    seed = seed.wrapping_add(1);
+    thread::spawn(move || match serve([first, stream], game_seed) {
+      Ok(winner) => println!("game {} won by player {}", game_seed, winner + 1),
+      Err(err) => eprintln!("game {} stopped: {}", game_seed, err),
+    });
+  }
+}
@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+
+use tetris::{Tetris, TetrisAction, FRAMES_PER_SECOND};
+
+use crate::{builder, land_garbage, Message};
+
+/**
+ * A player connected to a server. The own game runs here without waiting for the server,
+ * the game of the opponent follows their stream as it is relayed
+ */
+pub struct Client {
+  stream: TcpStream,
+  incoming: Receiver<Message>,
+  pub game: Tetris,
+  pub opponent: Tetris,
+  attacks: VecDeque<(u64, u32, usize)>, // frame, lines and hole of the attacks that did not land yet
+  won: Option<bool>,
+}
+
+impl Client {
+  /**
+   * Connects and waits until the server found an opponent
+   */
+  pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+    Self::new(TcpStream::connect(addr)?)
+  }
+
+  /**
+   * Waits on a connection to the server until it found an opponent
+   */
+  pub fn new(stream: TcpStream) -> io::Result<Self> {
+    let reader = BufReader::new(stream.try_clone()?);
+    let (tx, incoming) = mpsc::channel();
+    thread::spawn(move || {
+      for message in reader.lines().map_while(|line| line.ok()?.parse::<Message>().ok()) {
+        if tx.send(message).is_err() {
+          return;
+        }
+      }
+    });
+
+    let seed = match incoming.recv() {
+      Ok(Message::Start { seed }) => seed,
+      _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "server did not start a game")),
+    };
+    Ok(Self {
+      stream,
+      incoming,
+      game: builder(seed).build(),
+      opponent: builder(seed).build(),
+      attacks: VecDeque::new(),
+      won: None,
+    })
+  }
+
+  /**
+   * Whether the game was won, None while it is going on
+   */
+  pub fn won(&self) -> Option<bool> {
+    self.won
+  }
+
+  pub fn do_action(&mut self, action: TetrisAction) -> io::Result<()> {
+    if self.won.is_some() || self.game.phase().is_over() {
+      return Ok(());
+    }
+    let pieces = self.game.stats().pieces;
+    self.game.do_action(action);
+    self.send(Message::Act { frame: self.game.stats().frames, action })?;
+    self.land(pieces)
+  }
+
+  /**
+   * Advances the own game by a frame and handles what the server sent, see `FRAMES_PER_SECOND`
+   */
+  pub fn tick(&mut self) -> io::Result<()> {
+    self.poll()?;
+    if self.won.is_some() || self.game.phase().is_over() {
+      return Ok(());
+    }
+    let pieces = self.game.stats().pieces;
+    self.game.tick();
+    self.land(pieces)?;
+    // the server only sees the game move on with the stream, so it hears from idle players every second and on a top out
+    let frame = self.game.stats().frames;
+    if frame.is_multiple_of(FRAMES_PER_SECOND as u64) || self.game.phase().is_over() {
+      self.send(Message::Tick { frame })?;
+    }
+    Ok(())
+  }
+
+  /**
+   * Takes the lines sent by the opponent and follows their game, without waiting for more messages
+   */
+  pub fn poll(&mut self) -> io::Result<()> {
+    loop {
+      match self.incoming.try_recv() {
+        Ok(message) => self.receive(message)?,
+        Err(TryRecvError::Empty) => return Ok(()),
+        Err(TryRecvError::Disconnected) => return self.closed(),
+      }
+    }
+  }
+
+  /**
+   * Waits until the server decided the game, returns whether it was won
+   */
+  pub fn wait(&mut self) -> io::Result<bool> {
+    while self.won.is_none() {
+      match self.incoming.recv() {
+        Ok(message) => self.receive(message)?,
+        Err(_) => self.closed()?,
+      }
+    }
+    Ok(self.won.unwrap())
+  }
+
+  fn receive(&mut self, message: Message) -> io::Result<()> {
+    match message {
+      Message::Attack { frame, lines, hole } => self.attacks.push_back((frame, lines, hole)),
+      Message::Win => self.won = Some(true),
+      Message::Lose => self.won = Some(false),
+      message => {
+        message.apply(&mut self.opponent);
+      }
+    }
+    Ok(())
+  }
+
+  // garbage lands right after a lock, the server lands it on its copy at the lock the stream says
+  fn land(&mut self, pieces: u32) -> io::Result<()> {
+    for message in land_garbage(&mut self.game, &mut self.attacks, pieces) {
+      self.send(message)?;
+    }
+    Ok(())
+  }
+
+  fn send(&mut self, message: Message) -> io::Result<()> {
+    writeln!(self.stream, "{}", message)
+  }
+
+  fn closed(&self) -> io::Result<()> {
+    match self.won {
+      Some(_) => Ok(()),
+      None => Err(io::Error::new(io::ErrorKind::ConnectionAborted, "server closed the connection")),
+    }
+  }
+}
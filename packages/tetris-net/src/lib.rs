@@ -0,0 +1,100 @@
+mod client;
+mod protocol;
+mod server;
+
+pub use client::Client;
+pub use protocol::{builder, land_garbage, Message, ParseMessageError, GARBAGE_DELAY};
+pub use server::{serve, Match};
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use std::io;
+  use std::net::{TcpListener, TcpStream};
+  use std::thread::{self, JoinHandle};
+  use std::time::Duration;
+  use tetris::TetrisAction;
+  use tetris_bot::{Bot, HeuristicBot, Weights};
+
+  // the game starts once both are connected
+  fn start(seed: u64) -> (JoinHandle<io::Result<usize>>, Client, Client) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = thread::spawn(move || {
+      let first = listener.accept().unwrap().0;
+      let second = listener.accept().unwrap().0;
+      serve([first, second], seed)
+    });
+
+    let (first, second) = (TcpStream::connect(addr).unwrap(), TcpStream::connect(addr).unwrap());
+    (server, Client::new(first).unwrap(), Client::new(second).unwrap())
+  }
+
+  #[test]
+  fn plays_a_game_over_loopback() {
+    let (server, mut first, mut second) = start(11);
+    assert_eq!(first.game.queue(), second.game.queue());
+
+    // the second player stacks up in the middle while the first one waits
+    while !second.game.is_game_over() {
+      second.do_action(TetrisAction::HardDrop).unwrap();
+      second.tick().unwrap();
+      first.tick().unwrap();
+    }
+    assert!(!second.wait().unwrap());
+    assert!(first.wait().unwrap());
+    assert_eq!(server.join().unwrap().unwrap(), 0);
+
+    // the first player followed the whole game of the second one
+    assert!(first.opponent.is_game_over());
+    assert_eq!(first.opponent.board(), second.game.board());
+    assert_eq!(first.opponent.stats().pieces, second.game.stats().pieces);
+  }
+
+  #[test]
+  fn takes_late_garbage_over_loopback() {
+    let (server, mut first, mut second) = start(11);
+
+    // the first player clears lines with a bot until they send some
+    let mut bot = HeuristicBot::new(Weights::default());
+    while first.game.stats().attack == 0 {
+      assert!(first.game.stats().pieces < 100, "no lines were sent");
+      for action in bot.choose(&first.game).unwrap().actions {
+        first.do_action(action).unwrap();
+      }
+      while !first.game.phase().has_block() {
+        first.tick().unwrap();
+      }
+    }
+    thread::sleep(Duration::from_millis(100));
+
+    // the attack is still on its way while the second player runs past the frame it is due and locks a block
+    for _ in 0..GARBAGE_DELAY + 60 {
+      second.game.tick();
+    }
+    second.do_action(TetrisAction::HardDrop).unwrap();
+    assert_eq!(second.game.garbage_left(), 0);
+
+    // so it lands on the next lock instead
+    while second.game.garbage_left() == 0 {
+      second.tick().unwrap();
+      if second.game.stats().frames % 60 == 0 {
+        second.do_action(TetrisAction::HardDrop).unwrap();
+      }
+    }
+    assert_eq!(second.won(), None);
+
+    while !second.game.is_game_over() {
+      second.do_action(TetrisAction::HardDrop).unwrap();
+      second.tick().unwrap();
+      first.tick().unwrap();
+    }
+    assert!(!second.wait().unwrap());
+    assert!(first.wait().unwrap());
+    assert_eq!(server.join().unwrap().unwrap(), 0);
+
+    // the garbage landed at the same lock on both sides
+    assert_eq!(first.opponent.board(), second.game.board());
+    assert_eq!(first.opponent.stats().pieces, second.game.stats().pieces);
+  }
+}
@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::str::FromStr;
+
+use tetris::{SevenBag, Tetris, TetrisAction, TetrisBuilder, FRAMES_PER_SECOND};
+
+/**
+ * Frames of the receiving game between the server sending an attack and the garbage landing,
+ * an attack that reaches the player later lands on their next lock
+ */
+pub const GARBAGE_DELAY: u64 = 2 * FRAMES_PER_SECOND as u64;
+
+/**
+ * Both players play this game, so the same seed deals the same blocks on both sides
+ */
+pub fn builder(seed: u64) -> TetrisBuilder {
+  TetrisBuilder::new(10, 20).with_seed(seed).with_piece_generator(Box::new(SevenBag::new()))
+}
+
+/**
+ * One line of the text protocol between the server and a client.
+ * `Act`, `Tick` and `Garbage` make up the stream of a player and carry the frame they happened on,
+ * the server checks them on its own copy of the game and relays them to the opponent
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Message {
+  Start { seed: u64 }, // sent to both players once they are paired
+  Act { frame: u64, action: TetrisAction },
+  Tick { frame: u64 }, // the game ran up to the frame without actions, so the other side can catch up
+  Attack { frame: u64, lines: u32, hole: usize }, // lines sent by the opponent, they land on the first lock on or after the frame, or on the next lock if they arrive later
+  Garbage { frame: u64, lines: u32, hole: usize }, // an attack landed on the lock of the frame, sent right after the lock so the server lands it there too
+  Win,
+  Lose,
+}
+
+impl Message {
+  /**
+   * Frame of a message of the stream of a player, None for the others
+   */
+  pub fn frame(&self) -> Option<u64> {
+    match *self {
+      Self::Act { frame, .. } | Self::Tick { frame } | Self::Garbage { frame, .. } => Some(frame),
+      _ => None,
+    }
+  }
+
+  /**
+   * Plays a message of the stream of a player on a copy of their game, ticking up to its frame first.
+   * Returns false if the message is not part of a stream or happened before the frame the game is at
+   */
+  pub fn apply(&self, tetris: &mut Tetris) -> bool {
+    let frame = match self.frame() {
+      Some(frame) if frame >= tetris.stats().frames => frame,
+      _ => return false,
+    };
+    while tetris.stats().frames < frame && !tetris.phase().is_over() {
+      tetris.tick();
+    }
+    match *self {
+      Self::Act { action, .. } => tetris.do_action(action),
+      Self::Garbage { lines, hole, .. } => tetris.add_garbage(lines as usize, hole),
+      _ => {}
+    }
+    true
+  }
+}
+
+/**
+ * Lands the attacks that are due if a block locked since the game had `pieces` pieces.
+ * Returns the `Garbage` messages of the attacks that landed, the server lands them on the same lock of its copy
+ */
+pub fn land_garbage(tetris: &mut Tetris, attacks: &mut VecDeque<(u64, u32, usize)>, pieces: u32) -> Vec<Message> {
+  let frame = tetris.stats().frames;
+  let mut landed = Vec::new();
+  if tetris.stats().pieces == pieces || tetris.phase().is_over() {
+    return landed;
+  }
+  while let Some((_, lines, hole)) = attacks.front().copied().filter(|&(from, _, _)| from <= frame) {
+    attacks.pop_front();
+    tetris.add_garbage(lines as usize, hole);
+    landed.push(Message::Garbage { frame, lines, hole });
+  }
+  landed
+}
+
+impl fmt::Display for Message {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Start { seed } => write!(f, "start {}", seed),
+      Self::Act { frame, action } => write!(f, "act {} {}", frame, action),
+      Self::Tick { frame } => write!(f, "tick {}", frame),
+      Self::Attack { frame, lines, hole } => write!(f, "attack {} {} {}", frame, lines, hole),
+      Self::Garbage { frame, lines, hole } => write!(f, "garbage {} {} {}", frame, lines, hole),
+      Self::Win => write!(f, "win"),
+      Self::Lose => write!(f, "lose"),
+    }
+  }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseMessageError(pub String); // the line that could not be parsed
+
+impl fmt::Display for ParseMessageError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "unknown message '{}'", self.0)
+  }
+}
+
+impl std::error::Error for ParseMessageError {}
+
+impl FromStr for Message {
+  type Err = ParseMessageError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let err = || ParseMessageError(String::from(s));
+    let words: Vec<&str> = s.split_whitespace().collect();
+    let number = |i: usize| words.get(i).and_then(|word| word.parse::<u64>().ok()).ok_or_else(err);
+
+    let message = match (words.first().copied(), words.len()) {
+      (Some("start"), 2) => Self::Start { seed: number(1)? },
+      (Some("act"), 3) => Self::Act {
+        frame: number(1)?,
+        action: words[2].parse().map_err(|_| err())?,
+      },
+      (Some("tick"), 2) => Self::Tick { frame: number(1)? },
+      (Some("attack"), 4) => Self::Attack {
+        frame: number(1)?,
+        lines: number(2)? as u32,
+        hole: number(3)? as usize,
+      },
+      (Some("garbage"), 4) => Self::Garbage {
+        frame: number(1)?,
+        lines: number(2)? as u32,
+        hole: number(3)? as usize,
+      },
+      (Some("win"), 1) => Self::Win,
+      (Some("lose"), 1) => Self::Lose,
+      _ => return Err(err()),
+    };
+    Ok(message)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tetris::MoveDirection;
+
+  #[test]
+  fn messages_round_trip() {
+    let messages = [
+      Message::Start { seed: 42 },
+      Message::Act { frame: 90, action: TetrisAction::Move(MoveDirection::Left) },
+      Message::Tick { frame: 120 },
+      Message::Attack { frame: 125, lines: 4, hole: 7 },
+      Message::Garbage { frame: 130, lines: 4, hole: 7 },
+      Message::Win,
+      Message::Lose,
+    ];
+    for message in messages.iter() {
+      assert_eq!(message.to_string().parse::<Message>().as_ref(), Ok(message));
+    }
+    assert_eq!("act 90".parse::<Message>(), Err(ParseMessageError(String::from("act 90"))));
+    assert!("act 90 up".parse::<Message>().is_err());
+  }
+}
@@ -0,0 +1,265 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpStream};
+use std::sync::mpsc;
+use std::thread;
+
+use tetris::{Pcg32, Tetris, TetrisEvent};
+
+use crate::{builder, Message, GARBAGE_DELAY};
+
+// holes come from their own stream so the blocks that are dealt do not change
+const HOLE_STREAM: u64 = 0x686f6c6573;
+
+/**
+ * Referee of a game between two players, it plays the stream of each player on its own copy of their game
+ * to send the lines they clear to the opponent and to decide who tops out first.
+ * Garbage lands on the copy at the lock the stream of the player takes it on, see `Message::Attack`
+ */
+pub struct Match {
+  games: [Tetris; 2],
+  attacks: [VecDeque<(u64, u32, usize)>; 2], // frame, lines and hole of the attacks sent to each player that did not land yet
+  locks: [Option<u64>; 2], // frame of the last lock on the copy of each player
+  holes: Pcg32,
+  winner: Option<usize>,
+}
+
+impl Match {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      games: [builder(seed).build(), builder(seed).build()],
+      attacks: [VecDeque::new(), VecDeque::new()],
+      locks: [None, None],
+      holes: Pcg32::with_stream(seed, HOLE_STREAM),
+      winner: None,
+    }
+  }
+
+  pub fn winner(&self) -> Option<usize> {
+    self.winner
+  }
+
+  pub fn game(&self, player: usize) -> &Tetris {
+    &self.games[player]
+  }
+
+  /**
+   * Plays the next message of the stream of a player, returns the messages to send and who to send them to.
+   * A message that does not belong in the stream, like garbage that was not due or not taken on a lock, forfeits the game
+   */
+  pub fn play(&mut self, player: usize, message: Message) -> Vec<(usize, Message)> {
+    if self.winner.is_some() {
+      return Vec::new();
+    }
+    let opponent = 1 - player;
+
+    let valid = match message {
+      Message::Act { frame, action } => self.advance(player, frame) && {
+        let pieces = self.games[player].stats().pieces;
+        self.games[player].do_action(action);
+        self.lock(player, pieces);
+        true
+      },
+      Message::Tick { frame } => self.advance(player, frame),
+      Message::Garbage { frame, lines, hole } => self.advance(player, frame) && self.land(player, frame, lines, hole),
+      _ => false,
+    };
+    if !valid {
+      return self.forfeit(player);
+    }
+    let mut out = vec![(opponent, message)];
+
+    let lines: u32 = self.games[player]
+      .drain_events()
+      .into_iter()
+      .filter_map(|event| match event {
+        TetrisEvent::LineClear(line_clear) => Some(line_clear.attack()),
+        _ => None,
+      })
+      .sum();
+    if lines > 0 {
+      let hole = self.holes.next_u32() as usize % self.games[opponent].dim.width;
+      let frame = self.games[opponent].stats().frames + GARBAGE_DELAY;
+      self.attacks[opponent].push_back((frame, lines, hole));
+      out.push((opponent, Message::Attack { frame, lines, hole }));
+    }
+
+    if self.games[player].is_game_over() {
+      out.extend(self.forfeit(player));
+    }
+    out
+  }
+
+  // ticks the copy of the player up to the frame
+  fn advance(&mut self, player: usize, frame: u64) -> bool {
+    if frame < self.games[player].stats().frames {
+      return false;
+    }
+    while self.games[player].stats().frames < frame && !self.games[player].phase().is_over() {
+      let pieces = self.games[player].stats().pieces;
+      self.games[player].tick();
+      self.lock(player, pieces);
+    }
+    true
+  }
+
+  // remembers the frame if a block locked since the copy had `pieces` pieces
+  fn lock(&mut self, player: usize, pieces: u32) {
+    if self.games[player].stats().pieces != pieces {
+      self.locks[player] = Some(self.games[player].stats().frames);
+    }
+  }
+
+  // the next attack lands if the player took it right after a lock on or after its frame,
+  // so an attack that reached the player late lands on a later lock than on time
+  fn land(&mut self, player: usize, frame: u64, lines: u32, hole: usize) -> bool {
+    let due = match self.attacks[player].front() {
+      Some(&(from, attack_lines, attack_hole)) => from <= frame && (attack_lines, attack_hole) == (lines, hole),
+      None => false,
+    };
+    if !due || self.locks[player] != Some(frame) || self.games[player].phase().is_over() {
+      return false;
+    }
+    self.attacks[player].pop_front();
+    self.games[player].add_garbage(lines as usize, hole);
+    true
+  }
+
+  /**
+   * Ends the game with a loss for the player, when they top out or leave
+   */
+  pub fn forfeit(&mut self, player: usize) -> Vec<(usize, Message)> {
+    if self.winner.is_some() {
+      return Vec::new();
+    }
+    self.winner = Some(1 - player);
+    vec![(player, Message::Lose), (1 - player, Message::Win)]
+  }
+}
+
+/**
+ * Runs a game between two connected players until one of them wins, returns the winner.
+ * Players that disconnect or send lines that are not messages lose
+ */
+pub fn serve(streams: [TcpStream; 2], seed: u64) -> io::Result<usize> {
+  let (tx, rx) = mpsc::channel();
+  for (player, stream) in streams.iter().enumerate() {
+    let reader = BufReader::new(stream.try_clone()?);
+    let tx = tx.clone();
+    thread::spawn(move || {
+      for line in reader.lines() {
+        match line.ok().and_then(|line| line.parse::<Message>().ok()) {
+          Some(message) => {
+            if tx.send((player, Some(message))).is_err() {
+              return;
+            }
+          }
+          None => break,
+        }
+      }
+      // None tells the game that the player is gone
+      let _ = tx.send((player, None));
+    });
+  }
+  drop(tx);
+
+  let mut streams = streams;
+  let mut game = Match::new(seed);
+  let mut out: Vec<(usize, Message)> = vec![(0, Message::Start { seed }), (1, Message::Start { seed })];
+  loop {
+    let mut gone = None;
+    for (player, message) in out.drain(..) {
+      if writeln!(streams[player], "{}", message).is_err() {
+        gone = Some(player);
+      }
+    }
+    // a player that can not be written to loses, the result still goes out to the other one
+    if let Some(player) = gone {
+      for (to, message) in game.forfeit(player) {
+        let _ = writeln!(streams[to], "{}", message);
+      }
+    }
+    if let Some(winner) = game.winner() {
+      for stream in streams.iter() {
+        let _ = stream.shutdown(Shutdown::Both);
+      }
+      return Ok(winner);
+    }
+
+    out = match rx.recv() {
+      Ok((player, Some(message))) => game.play(player, message),
+      Ok((player, None)) => game.forfeit(player),
+      Err(_) => unreachable!("the readers only stop after telling the game"),
+    };
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tetris::{BlockType, TetrisAction};
+
+  // the first player drops an O into a board with a double, the perfect clear sends 11 lines
+  fn attacked() -> (Match, usize) {
+    let mut game = Match::new(5);
+    let board = format!("{}GGGGxxGGGG\nGGGGxxGGGG", "xxxxxxxxxx\n".repeat(18));
+    game.games[0].set_board(board.parse().unwrap()).unwrap();
+    game.games[0].set_current(BlockType::O).unwrap();
+
+    let drop = Message::Act { frame: 0, action: TetrisAction::HardDrop };
+    let out = game.play(0, drop);
+    let hole = match out.as_slice() {
+      [(1, relayed), (1, Message::Attack { frame: GARBAGE_DELAY, lines: 11, hole })] if *relayed == drop => *hole,
+      out => panic!("unexpected messages {:?}", out),
+    };
+    (game, hole)
+  }
+
+  #[test]
+  fn sends_cleared_lines_and_decides_the_winner() {
+    let (mut game, hole) = attacked();
+
+    // it lands when the stream takes it right after a lock that is not before the frame
+    let frame = GARBAGE_DELAY + 10;
+    let drop = Message::Act { frame, action: TetrisAction::HardDrop };
+    assert_eq!(game.play(1, drop), vec![(0, drop)]);
+    assert_eq!(game.game(1).garbage_left(), 0);
+    let garbage = Message::Garbage { frame, lines: 11, hole };
+    assert_eq!(game.play(1, garbage), vec![(0, garbage)]);
+    assert_eq!(game.game(1).garbage_left(), 11);
+    assert_eq!(game.game(1).board().get(hole, 19), 0);
+    assert_eq!(game.game(1).board().get((hole + 1) % 10, 19), tetris::GARBAGE);
+
+    // but only once
+    assert_eq!(game.play(1, garbage), vec![(1, Message::Lose), (0, Message::Win)]);
+    assert_eq!(game.winner(), Some(0));
+    assert!(game.play(0, drop).is_empty());
+  }
+
+  #[test]
+  fn garbage_lands_on_a_lock_once_it_is_due() {
+    // not before the frame of the attack
+    let (mut game, hole) = attacked();
+    let frame = GARBAGE_DELAY - 10;
+    game.play(1, Message::Act { frame, action: TetrisAction::HardDrop });
+    let garbage = Message::Garbage { frame, lines: 11, hole };
+    assert_eq!(game.play(1, garbage), vec![(1, Message::Lose), (0, Message::Win)]);
+
+    // not between locks
+    let (mut game, hole) = attacked();
+    game.play(1, Message::Act { frame: GARBAGE_DELAY, action: TetrisAction::HardDrop });
+    let garbage = Message::Garbage { frame: GARBAGE_DELAY + 5, lines: 11, hole };
+    assert_eq!(game.play(1, garbage), vec![(1, Message::Lose), (0, Message::Win)]);
+
+    // an attack that reached the player late lands on their next lock
+    let (mut game, hole) = attacked();
+    let tick = Message::Tick { frame: GARBAGE_DELAY + 60 };
+    assert_eq!(game.play(1, tick), vec![(0, tick)]);
+    let frame = GARBAGE_DELAY + 70;
+    game.play(1, Message::Act { frame, action: TetrisAction::HardDrop });
+    let garbage = Message::Garbage { frame, lines: 11, hole };
+    assert_eq!(game.play(1, garbage), vec![(0, garbage)]);
+    assert_eq!(game.game(1).garbage_left(), 11);
+    assert_eq!(game.winner(), None);
+  }
+}
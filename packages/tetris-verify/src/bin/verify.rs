@@ -0,0 +1,51 @@
+//! Plays submitted games again and prints which scores are real, exits with 1 if any is not
+//!
+//! cargo run --release -p tetris-verify --bin verify -- submission.txt
+
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::process;
+
+use tetris_verify::{verify, Submission};
+
+// reason the submission is rejected, if it is
+fn check(text: &str) -> Result<String, String> {
+  let submission: Submission = text.parse().map_err(|err| format!("{}", err))?;
+  let stats = verify(&submission).map_err(|err| format!("{}", err))?;
+  Ok(format!("score {}, {} lines, {} pieces", submission.score, stats.lines, stats.pieces))
+}
+
+fn main() {
+  let files: Vec<String> = env::args().skip(1).collect();
+  if files.iter().any(|file| file.starts_with("--")) {
+    eprintln!("usage: verify [FILE]...");
+    eprintln!("reads one submission from each file, or from stdin without files");
+    process::exit(2);
+  }
+
+  let texts: Vec<(String, io::Result<String>)> = if files.is_empty() {
+    let mut text = String::new();
+    let read = io::stdin().read_to_string(&mut text).map(|_| text);
+    vec![(String::from("stdin"), read)]
+  } else {
+    files.into_iter().map(|file| {
+      let read = fs::read_to_string(&file);
+      (file, read)
+    }).collect()
+  };
+
+  let mut rejected = false;
+  for (name, text) in texts {
+    match text.map_err(|err| format!("could not read: {}", err)).and_then(|text| check(&text)) {
+      Ok(summary) => println!("{}: accepted, {}", name, summary),
+      Err(reason) => {
+        println!("{}: rejected, {}", name, reason);
+        rejected = true;
+      }
+    }
+  }
+  if rejected {
+    process::exit(1);
+  }
+}
@@ -0,0 +1,157 @@
+use std::fmt;
+use std::str::FromStr;
+
+use tetris::{ParseReplayError, Replay, Stats, TetrisBuilder, FRAMES_PER_SECOND};
+
+/**
+ * Longest game that is played again, so a replay can not keep the verifier busy, three hours
+ */
+pub const MAX_FRAMES: u64 = 3 * 60 * 60 * FRAMES_PER_SECOND as u64;
+
+/**
+ * A score for the leaderboard together with the replay of the game it was played in,
+ * written as a line like `score 1200` followed by the replay
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Submission {
+  pub score: u64,
+  pub replay: Replay,
+}
+
+impl fmt::Display for Submission {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "score {}\n{}", self.score, self.replay)
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParseSubmissionError {
+  Score, // the first line is not the score
+  Replay(ParseReplayError),
+}
+
+impl fmt::Display for ParseSubmissionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Score => write!(f, "submission has to start with a line like score 1200"),
+      Self::Replay(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+impl std::error::Error for ParseSubmissionError {}
+
+impl FromStr for Submission {
+  type Err = ParseSubmissionError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.trim_start();
+    let (score, replay) = s.split_at(s.find('\n').unwrap_or(s.len()));
+    Ok(Self {
+      score: score
+        .trim()
+        .strip_prefix("score ")
+        .and_then(|score| score.trim().parse().ok())
+        .ok_or(ParseSubmissionError::Score)?,
+      replay: replay.parse().map_err(ParseSubmissionError::Replay)?,
+    })
+  }
+}
+
+/**
+ * Why a submission was rejected
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum VerifyError {
+  TooLong(u64), // frames of a replay that is longer than `MAX_FRAMES`
+  OutOfOrder, // the inputs are not in the order of their frames or come after the end
+  Frames { claimed: u64, actual: u64 }, // the game was over before the end of the replay
+  Score { claimed: u64, actual: u64 },
+}
+
+impl fmt::Display for VerifyError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Self::TooLong(frames) => write!(f, "replay of {} frames is longer than {} frames", frames, MAX_FRAMES),
+      Self::OutOfOrder => write!(f, "inputs are not in the order of their frames"),
+      Self::Frames { claimed, actual } => write!(f, "replay ends on frame {} but the game was over on frame {}", claimed, actual),
+      Self::Score { claimed, actual } => write!(f, "score {} was submitted but the replay scores {}", claimed, actual),
+    }
+  }
+}
+
+impl std::error::Error for VerifyError {}
+
+/**
+ * Plays the replay again and accepts the submission if the game ends the way it was submitted,
+ * returns the stats of the game
+ */
+pub fn verify(submission: &Submission) -> Result<Stats, VerifyError> {
+  let replay = &submission.replay;
+  if replay.frames > MAX_FRAMES {
+    return Err(VerifyError::TooLong(replay.frames));
+  }
+  let tetris = replay.play(TetrisBuilder::leaderboard()).ok_or(VerifyError::OutOfOrder)?;
+
+  let stats = *tetris.stats();
+  if stats.frames != replay.frames {
+    return Err(VerifyError::Frames { claimed: replay.frames, actual: stats.frames });
+  }
+  if tetris.score() != submission.score {
+    return Err(VerifyError::Score { claimed: submission.score, actual: tetris.score() });
+  }
+  Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use tetris_bot::{Bot, HeuristicBot, Weights};
+
+  // the bot plays a few seconds of a game, so it clears some lines
+  fn submission() -> Submission {
+    let mut tetris = TetrisBuilder::leaderboard().with_seed(8).build();
+    let mut replay = Replay::new(8);
+    let mut bot = HeuristicBot::new(Weights::default());
+    while tetris.stats().pieces < 30 {
+      let placement = bot.choose(&tetris).unwrap();
+      for action in placement.actions {
+        (0..3).for_each(|_| tetris.tick());
+        replay.record(&tetris, action);
+        tetris.do_action(action);
+      }
+      // the line clear and entry delays pass before the next block can be moved
+      while !tetris.phase().has_block() {
+        tetris.tick();
+      }
+    }
+    replay.finish(&tetris);
+    Submission {
+      score: tetris.score(),
+      replay,
+    }
+  }
+
+  #[test]
+  fn accepts_only_the_score_of_the_replay() {
+    let submission = submission();
+    assert!(submission.score > 0);
+    let text = submission.to_string();
+    assert_eq!(text.parse(), Ok(submission.clone()));
+    assert_eq!(verify(&text.parse().unwrap()).map(|stats| stats.frames), Ok(submission.replay.frames));
+
+    let mut faked = submission.clone();
+    faked.score += 100;
+    assert_eq!(verify(&faked), Err(VerifyError::Score { claimed: faked.score, actual: submission.score }));
+
+    // a different seed deals different blocks, so the same inputs play a different game
+    let mut faked = submission.clone();
+    faked.replay.seed += 1;
+    assert!(verify(&faked).is_err());
+
+    let mut faked = submission;
+    faked.replay.frames = MAX_FRAMES + 1;
+    assert_eq!(verify(&faked), Err(VerifyError::TooLong(MAX_FRAMES + 1)));
+    assert_eq!("1200\nseed 1\nend 0".parse::<Submission>(), Err(ParseSubmissionError::Score));
+  }
+}
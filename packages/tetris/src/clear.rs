@@ -0,0 +1,101 @@
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TSpin {
+  None,
+  Mini,
+  Full,
+}
+
+/**
+ * Describes a lock that cleared at least one line
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LineClear {
+  pub lines: u32,
+  pub t_spin: TSpin,
+  pub combo: u32, // number of clearing locks in a row before this one
+  pub back_to_back: bool, // whether this clear continued a back-to-back chain
+  pub perfect_clear: bool, // whether the playfield is empty after the clear
+}
+
+impl LineClear {
+
+  /**
+   * Guideline points at level 1
+   */
+  pub fn points(&self) -> u64 {
+    use TSpin::*;
+    let base = match (self.t_spin, self.lines) {
+      (None, 1) => 100,
+      (None, 2) => 300,
+      (None, 3) => 500,
+      (None, _) => 800,
+      (Mini, 1) => 200,
+      (Mini, _) => 400,
+      (Full, 1) => 800,
+      (Full, 2) => 1200,
+      (Full, _) => 1600,
+    };
+
+    // difficult clears are worth 1.5 times as much when chained
+    let base = if self.back_to_back { base * 3 / 2 } else { base };
+
+    let perfect_clear = match (self.perfect_clear, self.lines) {
+      (false, _) => 0,
+      (true, 1) => 800,
+      (true, 2) => 1200,
+      (true, 3) => 1800,
+      (true, _) => if self.back_to_back { 3200 } else { 2000 },
+    };
+
+    base + perfect_clear + 50 * self.combo as u64
+  }
+
+  /**
+   * Garbage lines sent to an opponent by guideline rules
+   */
+  pub fn attack(&self) -> u32 {
+    use TSpin::*;
+    let base = match (self.t_spin, self.lines) {
+      (None, 1) => 0,
+      (None, 2) => 1,
+      (None, 3) => 2,
+      (None, _) => 4,
+      (Mini, n) => n - 1,
+      (Full, n) => 2 * n,
+    };
+
+    let back_to_back = if self.back_to_back { 1 } else { 0 };
+
+    // combos start sending lines from the second clear in a row
+    let combo = match self.combo {
+      0..=1 => 0,
+      2..=4 => 1,
+      5..=6 => 2,
+      7..=8 => 3,
+      9..=11 => 4,
+      _ => 5,
+    };
+
+    let perfect_clear = if self.perfect_clear { 10 } else { 0 };
+
+    base + back_to_back + combo + perfect_clear
+  }
+}
+
+/**
+ * Points for a t-spin that did not clear any lines
+ */
+pub fn t_spin_points(t_spin: TSpin) -> u64 {
+  match t_spin {
+    TSpin::None => 0,
+    TSpin::Mini => 100,
+    TSpin::Full => 400,
+  }
+}
+
+/**
+ * Tetrises and t-spins that clear lines keep the back-to-back chain going
+ */
+pub fn is_difficult(lines: u32, t_spin: TSpin) -> bool {
+  lines >= 4 || (lines > 0 && !matches!(t_spin, TSpin::None))
+}
@@ -0,0 +1,166 @@
+use alloc::collections::VecDeque;
+
+use crate::{Block, BlockType, MoveDirection, Phase, RotationDirection, Tetris, TetrisAction};
+
+/**
+ * Everything that belongs to one of the two players of a cooperative game, the rest of the game is shared
+ */
+pub(crate) struct Player {
+  pub block: Block,
+  pub queue: VecDeque<BlockType>,
+  pub hold: Option<BlockType>,
+  pub hold_used: bool,
+  pub phase: Phase,
+  pub phase_timer: u32,
+  pub gravity: u32,
+  pub last_rotated: bool,
+  pub piece_inputs: u32,
+  pub initial_rotation: Option<RotationDirection>,
+  pub initial_hold: bool,
+  pub held: Option<(MoveDirection, u32)>,
+  pub columns: (isize, usize),
+}
+
+impl Player {
+  pub fn new(columns: (isize, usize)) -> Self {
+    Self {
+      block: Block::new(BlockType::I), // replaced by the first block in the queue
+      queue: VecDeque::new(),
+      hold: None,
+      hold_used: false,
+      phase: Phase::Falling,
+      phase_timer: 0,
+      gravity: 0,
+      last_rotated: false,
+      piece_inputs: 0,
+      initial_rotation: None,
+      initial_hold: false,
+      held: None,
+      columns,
+    }
+  }
+
+  /**
+   * Whether the active block of the player covers the cell
+   */
+  pub fn covers(&self, x: isize, y: isize) -> bool {
+    self.phase.has_block() && self.block.cells().contains(&(x, y))
+  }
+}
+
+/**
+ * The second player of a cooperative game is kept aside and swapped in while its blocks are moved,
+ * so both players go through the same code
+ */
+impl Tetris {
+  fn swap_partner(&mut self) {
+    use core::mem::swap;
+
+    let mut partner = match self.partner.take() {
+      Some(partner) => partner,
+      None => return,
+    };
+    swap(&mut self.curr_block, &mut partner.block);
+    swap(&mut self.queue, &mut partner.queue);
+    swap(&mut self.hold, &mut partner.hold);
+    swap(&mut self.hold_used, &mut partner.hold_used);
+    swap(&mut self.phase, &mut partner.phase);
+    swap(&mut self.phase_timer, &mut partner.phase_timer);
+    swap(&mut self.gravity, &mut partner.gravity);
+    swap(&mut self.last_rotated, &mut partner.last_rotated);
+    swap(&mut self.piece_inputs, &mut partner.piece_inputs);
+    swap(&mut self.initial_rotation, &mut partner.initial_rotation);
+    swap(&mut self.initial_hold, &mut partner.initial_hold);
+    swap(&mut self.held, &mut partner.held);
+    swap(&mut self.columns, &mut partner.columns);
+    self.partner = Some(partner);
+  }
+
+  /**
+   * Runs `f` with the given player as the current one, player 0 is the one the rest of the api refers to
+   */
+  pub(crate) fn as_player<R>(&mut self, player: usize, f: impl FnOnce(&mut Self) -> R) -> R {
+    let swapped = player == 1 && self.partner.is_some();
+    if swapped {
+      self.swap_partner();
+    }
+    let result = f(self);
+    if swapped {
+      self.swap_partner();
+    }
+    result
+  }
+
+  /**
+   * 2 for cooperative games, see `TetrisBuilder::with_coop`, otherwise 1
+   */
+  pub fn players(&self) -> usize {
+    if self.partner.is_some() { 2 } else { 1 }
+  }
+
+  /**
+   * Does the action for one of the players, player 0 is the same as `do_action`
+   */
+  pub fn do_player_action(&mut self, player: usize, action: TetrisAction) {
+    if player < self.players() {
+      self.as_player(player, |tetris| tetris.do_action(action));
+    }
+  }
+
+  /**
+   * Active block of a player, None while the player waits for a block or if there is no such player
+   */
+  pub fn player_block(&self, player: usize) -> Option<&Block> {
+    match (player, self.partner.as_ref()) {
+      (0, _) if self.phase.has_block() => Some(&self.curr_block),
+      (1, Some(partner)) if partner.phase.has_block() => Some(&partner.block),
+      _ => None,
+    }
+  }
+
+  pub(crate) fn partner_covers(&self, x: isize, y: isize) -> bool {
+    self.partner.as_ref().is_some_and(|partner| partner.covers(x, y))
+  }
+
+  pub(crate) fn zobrist_partner(&self) -> u64 {
+    self.partner.as_ref().map_or(0, |partner| {
+      let piece = if partner.phase.has_block() { self.zobrist.piece(&partner.block) } else { 0 };
+      self.zobrist.partner(piece ^ self.zobrist.hold(partner.hold) ^ self.zobrist.next(partner.queue.front().copied()))
+    })
+  }
+
+  /**
+   * Moves the active block of the partner up until it is clear of the stack again, after rows moved under it
+   */
+  pub(crate) fn lift_partner(&mut self) {
+    let mut partner = match self.partner.take() {
+      Some(partner) => partner,
+      None => return,
+    };
+    if partner.phase.has_block() {
+      let top = -(self.dim.height as isize);
+      while !self.fits(&partner.block) && partner.block.origin.1 > top {
+        partner.block.origin.1 -= 1;
+      }
+    }
+    self.partner = Some(partner);
+  }
+
+  /**
+   * Moves the active block of the partner up by the rows that were pushed up from the bottom
+   */
+  pub(crate) fn push_partner(&mut self, rows: usize) {
+    if let Some(partner) = self.partner.as_mut().filter(|partner| partner.phase.has_block()) {
+      partner.block.origin.1 -= rows as isize;
+    }
+  }
+
+  /**
+   * Ends the game for both players
+   */
+  pub(crate) fn end_partner(&mut self, phase: Phase) {
+    if let Some(partner) = self.partner.as_mut() {
+      partner.phase = phase;
+    }
+  }
+}
@@ -0,0 +1,108 @@
+use alloc::vec::Vec;
+
+use crate::{Pcg32, GARBAGE};
+
+// garbage holes come from their own stream so the blocks that are dealt do not change
+const GARBAGE_STREAM: u64 = 0x6761726261676521;
+
+/**
+ * Downstacking practice, the playfield starts with garbage rows and the game is finished once all of them are cleared
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Dig {
+  pub rows: usize, // garbage rows at the start
+  pub rise: Option<Rise>, // pushes more garbage up from the bottom over time
+  pub seed: u64, // the same seed gives the same holes
+}
+
+/**
+ * Timer of rising garbage, in frames, see `FRAMES_PER_SECOND`
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Rise {
+  pub interval: u32, // frames before the first row rises
+  pub min_interval: u32,
+  pub step: u32, // the interval gets this much shorter with every row
+}
+
+impl Dig {
+  pub fn new(rows: usize) -> Self {
+    Self {
+      rows,
+      rise: None,
+      seed: 0,
+    }
+  }
+
+  /**
+   * Cheese race, rows keep rising from the bottom starting every `interval` frames
+   */
+  pub fn with_rise(mut self, rise: Rise) -> Self {
+    self.rise = Some(rise);
+    self
+  }
+
+  pub fn with_seed(mut self, seed: u64) -> Self {
+    self.seed = seed;
+    self
+  }
+}
+
+/**
+ * Garbage generator and rise timer of a running game
+ */
+pub(crate) struct DigState {
+  pub dig: Dig,
+  rng: Pcg32,
+  last_hole: Option<usize>,
+  timer: u32, // frames since the last row rose
+  interval: u32,
+}
+
+impl DigState {
+  pub fn new(dig: Dig) -> Self {
+    Self {
+      dig,
+      rng: Pcg32::with_stream(dig.seed, GARBAGE_STREAM),
+      last_hole: None,
+      timer: 0,
+      interval: dig.rise.map_or(0, |rise| rise.interval),
+    }
+  }
+
+  /**
+   * Starts over with the same holes
+   */
+  pub fn reset(&mut self) {
+    *self = Self::new(self.dig);
+  }
+
+  /**
+   * A row of garbage with one hole, the hole is never in the same column as in the row before
+   */
+  pub fn row(&mut self, width: usize) -> Vec<u32> {
+    let mut hole = self.rng.next_u32() as usize % width;
+    if width > 1 && self.last_hole == Some(hole) {
+      hole = (hole + 1 + self.rng.next_u32() as usize % (width - 1)) % width;
+    }
+    self.last_hole = Some(hole);
+    (0..width).map(|x| if x == hole { 0 } else { GARBAGE }).collect()
+  }
+
+  /**
+   * Advances the rise timer by a frame, returns true when a row should rise
+   */
+  pub fn tick(&mut self) -> bool {
+    let rise = match self.dig.rise {
+      Some(rise) => rise,
+      None => return false,
+    };
+    self.timer += 1;
+    if self.timer < self.interval {
+      return false;
+    }
+    self.timer = 0;
+    self.interval = self.interval.saturating_sub(rise.step).max(rise.min_interval);
+    true
+  }
+}
@@ -0,0 +1,315 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{Block, BlockType, Board, ParseBoardError, Phase, Quiz, Tetris, TetrisBuilder, GARBAGE};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum EditError {
+  OutOfBounds(usize, usize), // cell that is not on the playfield
+  InvalidValue(u32), // not a block type, garbage or empty
+  SizeMismatch(usize, usize), // width and height of a board that does not match the playfield
+  Blocked, // the current block does not fit, not even at its spawn position
+}
+
+impl fmt::Display for EditError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Self::OutOfBounds(x, y) => write!(f, "cell ({}, {}) is outside of the playfield", x, y),
+      Self::InvalidValue(val) => write!(f, "{} is not a playfield value", val),
+      Self::SizeMismatch(width, height) => write!(f, "board is {}x{} instead of the size of the playfield", width, height),
+      Self::Blocked => write!(f, "the current block does not fit on the playfield"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EditError {}
+
+/**
+ * Position to practice, written as a quiz line followed by the board diagram:
+ *
+ * ```text
+ * #Q=[hold](current)next
+ * xxxxxxxxxx
+ * ZZxxxxxxxx
+ * ```
+ *
+ * Only the type of the current block is kept, it is played from its spawn position again.
+ * `Tetris::to_fumen` keeps where the block is, like a block that was put into a slot with `Tetris::set_block`
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Puzzle {
+  pub board: Board,
+  pub current: BlockType,
+  pub hold: Option<BlockType>,
+  pub next: Vec<BlockType>,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParsePuzzleError {
+  Queue, // the first line is not a quiz line with a current block
+  Board(ParseBoardError),
+}
+
+impl fmt::Display for ParsePuzzleError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self {
+      Self::Queue => write!(f, "puzzle has to start with a line like #Q=[hold](current)next"),
+      Self::Board(err) => write!(f, "{}", err),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePuzzleError {}
+
+impl fmt::Display for Puzzle {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let quiz = Quiz {
+      hold: self.hold,
+      current: Some(self.current),
+      next: self.next.clone(),
+    };
+    write!(f, "{}\n{}", quiz, self.board)
+  }
+}
+
+impl FromStr for Puzzle {
+  type Err = ParsePuzzleError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let s = s.trim_start();
+    let (quiz, board) = s.split_at(s.find('\n').unwrap_or(s.len()));
+    let quiz: Quiz = quiz.trim().parse().map_err(|_| ParsePuzzleError::Queue)?;
+    Ok(Self {
+      board: board.parse().map_err(ParsePuzzleError::Board)?,
+      current: quiz.current.ok_or(ParsePuzzleError::Queue)?,
+      hold: quiz.hold,
+      next: quiz.next,
+    })
+  }
+}
+
+/**
+ * Editing for practice tools, every edit checks that the active blocks still fit and resumes play from the edited state.
+ * Edits can not be undone and forget the locks that could be
+ */
+impl Tetris {
+  pub fn set_cell(&mut self, x: usize, y: usize, value: u32) -> Result<(), EditError> {
+    if x >= self.dim.width || y >= self.dim.height {
+      return Err(EditError::OutOfBounds(x, y));
+    }
+    if value > GARBAGE {
+      return Err(EditError::InvalidValue(value));
+    }
+    self.skip_delays();
+    let mut board = self.board();
+    board.set(x, y, value);
+    self.set_board(board)
+  }
+
+  /**
+   * Replaces the playfield, each active block goes back to its spawn position if it overlaps the new cells
+   */
+  pub fn set_board(&mut self, board: Board) -> Result<(), EditError> {
+    if (board.width, board.height) != (self.dim.width, self.dim.height) {
+      return Err(EditError::SizeMismatch(board.width, board.height));
+    }
+    if let Some(&value) = board.cells.iter().find(|&&value| value > GARBAGE) {
+      return Err(EditError::InvalidValue(value));
+    }
+
+    self.skip_delays();
+    let old = core::mem::replace(&mut *self.playfield.borrow_mut(), board.cells);
+    let partner = self.partner.as_ref().filter(|partner| partner.phase.has_block()).map(|partner| partner.block);
+    if partner.is_some() {
+      if let Err(err) = self.as_player(1, |tetris| tetris.place(tetris.refit())) {
+        *self.playfield.borrow_mut() = old;
+        return Err(err);
+      }
+    }
+    if let Err(err) = self.place(self.refit()) {
+      *self.playfield.borrow_mut() = old;
+      if let (Some(partner), Some(block)) = (self.partner.as_mut(), partner) {
+        partner.block = block;
+      }
+      return Err(err);
+    }
+    self.zobrist.rehash(&self.playfield.borrow());
+    Ok(())
+  }
+
+  /**
+   * Replaces the current block with a new one at the spawn position
+   */
+  pub fn set_current(&mut self, block_type: BlockType) -> Result<(), EditError> {
+    self.skip_delays();
+    self.place(self.spawn_position(block_type))
+  }
+
+  /**
+   * Puts the current block anywhere it fits, like into a t-spin slot
+   */
+  pub fn set_block(&mut self, block: Block) -> Result<(), EditError> {
+    self.skip_delays();
+    self.place(block)
+  }
+
+  pub fn set_hold(&mut self, hold: Option<BlockType>) {
+    self.skip_delays();
+    self.hold = hold;
+    self.hold_used = false;
+    self.edited();
+  }
+
+  /**
+   * Replaces the upcoming blocks, the piece generator continues after them
+   */
+  pub fn set_queue(&mut self, queue: Vec<BlockType>) {
+    self.skip_delays();
+    self.queue = VecDeque::from(queue);
+    // blocks that were logged for undo would be dealt again instead of the new queue
+    if let Some(history) = self.history.as_mut() {
+      history.reset(&self.queue);
+    }
+    self.edited();
+  }
+
+  /**
+   * Copy of the board, the type of the current block, hold and queue, see `TetrisBuilder::with_puzzle` to play it again
+   */
+  pub fn puzzle(&self) -> Puzzle {
+    Puzzle {
+      board: self.board(),
+      current: self.curr_block.block_type,
+      hold: self.hold,
+      next: self.queue.iter().copied().collect(),
+    }
+  }
+
+  // the current block stays if it still fits, otherwise it goes back to its spawn position
+  fn refit(&self) -> Block {
+    if self.fits(&self.curr_block) { self.curr_block } else { self.spawn_position(self.curr_block.block_type) }
+  }
+
+  fn place(&mut self, block: Block) -> Result<(), EditError> {
+    if !self.fits(&block) {
+      return Err(EditError::Blocked);
+    }
+    self.curr_block = block;
+    self.last_rotated = false;
+    self.gravity = 0;
+    self.edited();
+    Ok(())
+  }
+
+  // a block is in play after every edit, the game continues even if it was over
+  fn edited(&mut self) {
+    if !self.phase.has_block() && self.fits(&self.curr_block) {
+      self.set_phase(Phase::Falling);
+    }
+    self.pending_lock = None;
+    self.initial_rotation = None;
+    self.initial_hold = false;
+    if let Some(history) = self.history.as_mut() {
+      history.clear();
+    }
+    if let Some(lock_frames) = self.lock_frames.as_mut() {
+      lock_frames.fill(self.stats.frames);
+    }
+  }
+}
+
+impl TetrisBuilder {
+  /**
+   * Starts from the board, hold and queue of the puzzle, the dimensions are taken from the board
+   */
+  pub fn with_puzzle(self, puzzle: &Puzzle) -> Self {
+    self
+      .with_board(puzzle.board.clone())
+      .with_hold(puzzle.hold)
+      .with_queue(core::iter::once(puzzle.current).chain(puzzle.next.iter().copied()).collect())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{MoveDirection, RotationDirection, TetrisAction};
+
+  #[test]
+  fn edits_and_exports_a_puzzle() {
+    let mut tetris = TetrisBuilder::new(10, 4).with_history(10).build();
+    tetris.do_action(TetrisAction::HardDrop);
+    assert_eq!(tetris.set_cell(10, 0, 1), Err(EditError::OutOfBounds(10, 0)));
+    assert_eq!(tetris.set_cell(0, 0, 9), Err(EditError::InvalidValue(9)));
+    assert_eq!(tetris.set_board(Board::new(4, 4)), Err(EditError::SizeMismatch(4, 4)));
+
+    tetris.set_board("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      ZZZZxxZZZZ
+    ".parse().unwrap()).unwrap();
+    tetris.set_current(BlockType::O).unwrap();
+    tetris.set_hold(Some(BlockType::T));
+    tetris.set_queue(vec![BlockType::I, BlockType::L]);
+    assert!(!tetris.undo());
+
+    // painting under the current block sends it back to spawn, or fails if the spawn is blocked too
+    let spawn = tetris.curr_block;
+    tetris.do_action(TetrisAction::Move(MoveDirection::Down));
+    tetris.do_action(TetrisAction::Move(MoveDirection::Down));
+    let (x, y) = tetris.curr_block.cells()[3];
+    tetris.set_cell(x as usize, y as usize, GARBAGE).unwrap();
+    assert_eq!(tetris.curr_block, spawn);
+    tetris.set_cell(x as usize, y as usize, 0).unwrap();
+    let (x, y) = tetris.curr_block.cells()[0];
+    assert_eq!(tetris.set_cell(x as usize, y as usize, GARBAGE), Err(EditError::Blocked));
+    assert_eq!(tetris.board().get(x as usize, y as usize), 0);
+
+    let puzzle = tetris.puzzle();
+    let text = puzzle.to_string();
+    assert!(text.starts_with("#Q=[T](O)IL"));
+    assert_eq!(text.parse::<Puzzle>(), Ok(puzzle.clone()));
+    assert!(tetris.to_fumen().is_ok());
+
+    // the puzzle plays like the edited game
+    let mut copy = TetrisBuilder::new(0, 0).with_puzzle(&puzzle).build();
+    for tetris in [&mut tetris, &mut copy].iter_mut() {
+      tetris.do_action(TetrisAction::HardDrop);
+    }
+    assert_eq!(copy.board(), tetris.board());
+    assert_eq!(copy.curr_block, tetris.curr_block);
+    assert_eq!("ZZ".parse::<Puzzle>(), Err(ParsePuzzleError::Queue));
+
+    // in a cooperative game the block of the second player is checked as well
+    let mut tetris = TetrisBuilder::new(20, 8).with_coop().build();
+    let spawn = *tetris.player_block(1).unwrap();
+    tetris.do_player_action(1, TetrisAction::Move(MoveDirection::Down));
+    tetris.do_player_action(1, TetrisAction::Move(MoveDirection::Down));
+    let (x, y) = tetris.player_block(1).unwrap().cells()[3];
+    tetris.set_cell(x as usize, y as usize, GARBAGE).unwrap();
+    assert_eq!(tetris.player_block(1), Some(&spawn));
+    let (x, y) = spawn.cells()[0];
+    assert_eq!(tetris.set_cell(x as usize, y as usize, GARBAGE), Err(EditError::Blocked));
+    assert_eq!(tetris.board().get(x as usize, y as usize), 0);
+  }
+
+  #[test]
+  fn puzzles_respawn_the_current_block() {
+    let mut tetris = TetrisBuilder::new(10, 4).with_queue(vec![BlockType::T, BlockType::I]).build();
+    let spawn = tetris.curr_block;
+    let turned = tetris.try_action(&spawn, TetrisAction::Rotate(RotationDirection::Clockwise)).unwrap();
+    tetris.set_block(tetris.try_action(&turned, TetrisAction::Move(MoveDirection::Down)).unwrap()).unwrap();
+
+    let puzzle: Puzzle = tetris.puzzle().to_string().parse().unwrap();
+    assert_eq!(puzzle.current, BlockType::T);
+    let copy = TetrisBuilder::new(0, 0).with_puzzle(&puzzle).build();
+    assert_eq!(copy.board(), tetris.board());
+    assert_eq!(copy.curr_block, spawn);
+    assert_ne!(copy.curr_block, tetris.curr_block);
+  }
+}
@@ -0,0 +1,138 @@
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Block, BlockType, MoveDirection, RotationDirection, Tetris, TetrisAction, TetrisEvent};
+
+/**
+ * A block that was placed with more inputs than needed
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FinesseFault {
+  pub block_type: BlockType,
+  pub inputs: u32, // moves and rotations used, drops are not counted
+  pub optimal: u32, // fewest moves and rotations that hard drop into the same cells
+}
+
+// actions that count as finesse inputs, drops and holds are free
+const INPUTS: [TetrisAction; 4] = [
+  TetrisAction::Move(MoveDirection::Left),
+  TetrisAction::Move(MoveDirection::Right),
+  TetrisAction::Rotate(RotationDirection::Clockwise),
+  TetrisAction::Rotate(RotationDirection::CounterClockwise),
+];
+
+// pressing a direction is one input however far the block repeats
+pub(crate) fn is_input(action: TetrisAction) -> bool {
+  match action {
+    TetrisAction::Press(dir) => INPUTS.contains(&TetrisAction::Move(dir)),
+    action => INPUTS.contains(&action),
+  }
+}
+
+fn sorted_cells(block: &Block) -> [(isize, isize); 4] {
+  let mut cells = block.cells();
+  cells.sort_unstable();
+  cells
+}
+
+impl Tetris {
+  /**
+   * Fewest inputs that take a block from its spawn position to a hard drop into the cells of `placed`,
+   * None if the block can only get there with soft drops, like tucks and spins
+   */
+  pub fn optimal_inputs(&self, placed: &Block) -> Option<u32> {
+    let target = sorted_cells(placed);
+    let spawn = self.spawn_position(placed.block_type);
+    if !self.fits(&spawn) {
+      return None;
+    }
+
+    // breadth first over the positions at spawn height, so the first match has the fewest inputs
+    let mut seen: Vec<Block> = vec![spawn];
+    let mut queue = VecDeque::new();
+    queue.push_back((spawn, 0));
+    while let Some((block, inputs)) = queue.pop_front() {
+      let mut dropped = block;
+      while let Some(next) = self.try_action(&dropped, TetrisAction::Move(MoveDirection::Down)) {
+        dropped = next;
+      }
+      if sorted_cells(&dropped) == target {
+        return Some(inputs);
+      }
+
+      for &action in INPUTS.iter() {
+        if let Some(next) = self.try_action(&block, action) {
+          if !seen.contains(&next) {
+            seen.push(next);
+            queue.push_back((next, inputs + 1));
+          }
+        }
+      }
+    }
+    None
+  }
+
+  /**
+   * Compares the inputs of the current block with the optimal ones, before it becomes part of the playfield
+   */
+  pub(crate) fn check_finesse(&mut self) {
+    let optimal = match self.optimal_inputs(&self.curr_block) {
+      Some(optimal) => optimal,
+      None => return,
+    };
+    if self.piece_inputs > optimal {
+      self.stats.finesse_faults += 1;
+      self.events.push(TetrisEvent::FinesseFault(FinesseFault {
+        block_type: self.curr_block.block_type,
+        inputs: self.piece_inputs,
+        optimal,
+      }));
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{Srs, TetrisBuilder};
+
+  fn faults(tetris: &mut Tetris) -> Vec<FinesseFault> {
+    tetris
+      .drain_events()
+      .into_iter()
+      .filter_map(|event| match event {
+        TetrisEvent::FinesseFault(fault) => Some(fault),
+        _ => None,
+      })
+      .collect()
+  }
+
+  #[test]
+  fn reports_extra_inputs() {
+    use MoveDirection::*;
+    use TetrisAction::*;
+
+    let mut tetris = TetrisBuilder::new(10, 20)
+      .with_rotation_system(Box::new(Srs))
+      .with_queue(vec![BlockType::T; 3])
+      .build();
+
+    // three taps to the wall is optimal
+    (0..3).for_each(|_| tetris.do_action(Move(Left)));
+    tetris.do_action(HardDrop);
+    assert!(faults(&mut tetris).is_empty());
+
+    // going the wrong way first costs two inputs
+    tetris.do_action(Move(Right));
+    (0..4).for_each(|_| tetris.do_action(Move(Left)));
+    tetris.do_action(HardDrop);
+    assert_eq!(faults(&mut tetris), vec![FinesseFault { block_type: BlockType::T, inputs: 5, optimal: 3 }]);
+
+    // a full turn ends where it started
+    (0..4).for_each(|_| tetris.do_action(Rotate(RotationDirection::Clockwise)));
+    tetris.do_action(HardDrop);
+    assert_eq!(faults(&mut tetris), vec![FinesseFault { block_type: BlockType::T, inputs: 4, optimal: 0 }]);
+    assert_eq!(tetris.stats().finesse_faults, 2);
+  }
+}
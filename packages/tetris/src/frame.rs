@@ -0,0 +1,159 @@
+use alloc::vec::Vec;
+
+use crate::{Block, BlockType, MoveDirection, Phase, Tetris};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum CellKind {
+  Empty,
+  Locked,
+  Active, // part of the current block
+  Ghost, // where the current block would land
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Cell {
+  pub kind: CellKind,
+  pub value: u32, // playfield value, the block type of active and ghost cells
+}
+
+impl Cell {
+  pub fn block_type(&self) -> Option<BlockType> {
+    BlockType::from_value(self.value)
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Status {
+  Playing,
+  GameOver,
+  Finished,
+}
+
+/**
+ * Queue and hold of the second player of a cooperative game
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct PartnerFrame {
+  pub queue: Vec<BlockType>,
+  pub hold: Option<BlockType>,
+}
+
+/**
+ * Everything a renderer needs to draw the game, taken at one point in time
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+  pub width: usize,
+  pub height: usize,
+  pub cells: Vec<Cell>, // visible playfield, row by row from the top
+  pub queue: Vec<BlockType>, // upcoming blocks, the next block first
+  pub hold: Option<BlockType>,
+  pub score: u64,
+  pub lines: u32,
+  pub level: u32,
+  pub status: Status,
+  pub phase: Phase,
+  pub clearing: Vec<usize>, // rows that are being cleared during the line clear delay, from the top
+  pub partner: Option<PartnerFrame>, // the active cells of both players are drawn on the playfield
+}
+
+impl Frame {
+  pub fn get(&self, x: usize, y: usize) -> Cell {
+    self.cells[y * self.width + x]
+  }
+}
+
+impl Tetris {
+  /**
+   * Width and height of the frame, twice the playfield with the big modifier
+   */
+  pub fn frame_size(&self) -> (usize, usize) {
+    let scale = self.modifiers.scale();
+    (self.dim.width * scale, self.dim.height * scale)
+  }
+
+  pub fn frame(&self) -> Frame {
+    let (width, height) = (self.dim.width, self.dim.height);
+    let mut cells: Vec<Cell> = self
+      .playfield
+      .borrow()
+      .iter()
+      .map(|&value| Cell {
+        kind: if value == 0 { CellKind::Empty } else { CellKind::Locked },
+        value,
+      })
+      .collect();
+
+    let clearing = if self.phase == Phase::LineClearAnimating {
+      cells.chunks_exact(width).enumerate().filter(|(_, row)| row.iter().all(|cell| cell.kind == CellKind::Locked)).map(|(y, _)| y).collect()
+    } else {
+      Vec::new()
+    };
+
+    // locked cells that were there for long enough are hidden
+    if let (Some(delay), Some(lock_frames)) = (self.modifiers.invisible, self.lock_frames.as_ref()) {
+      for (cell, &frame) in cells.iter_mut().zip(lock_frames.iter()) {
+        if self.stats.frames - frame >= delay as u64 {
+          *cell = Cell { kind: CellKind::Empty, value: 0 };
+        }
+      }
+    }
+
+    // cells above the playfield are not visible, the active blocks are drawn over the ghosts
+    let mut set = |block: &Block, kind: CellKind| {
+      let value = block.block_type as u32;
+      for &(x, y) in block.cells().iter() {
+        if y >= 0 && (kind == CellKind::Active || cells[y as usize * width + x as usize].kind == CellKind::Empty) {
+          cells[y as usize * width + x as usize] = Cell { kind, value };
+        }
+      }
+    };
+
+    // the block that topped out is still drawn, during the delays the last block is already part of the playfield
+    let block = &self.curr_block;
+    if self.phase.has_block() {
+      set(&landing(block, |x, y| self.is_free(x, y)), CellKind::Ghost);
+    }
+    if let Some(partner) = self.partner.as_ref().filter(|partner| partner.phase.has_block()) {
+      let free = |x, y| self.is_open(x, y) && !(self.phase.has_block() && block.cells().contains(&(x, y)));
+      set(&landing(&partner.block, free), CellKind::Ghost);
+      set(&partner.block, CellKind::Active);
+    }
+    if self.phase.has_block() || self.phase == Phase::GameOver {
+      set(block, CellKind::Active);
+    }
+
+    self.modifiers.apply(Frame {
+      width,
+      height,
+      cells,
+      queue: self.queue.iter().copied().collect(),
+      hold: self.hold,
+      score: self.score,
+      lines: self.lines,
+      level: self.level,
+      status: match self.phase {
+        Phase::GameOver => Status::GameOver,
+        Phase::Finished => Status::Finished,
+        _ => Status::Playing,
+      },
+      phase: self.phase,
+      clearing,
+      partner: self.partner.as_ref().map(|partner| PartnerFrame {
+        queue: partner.queue.iter().copied().collect(),
+        hold: partner.hold,
+      }),
+    })
+  }
+}
+
+/**
+ * Drops a copy of the block as far as it goes
+ */
+fn landing(block: &Block, free: impl Fn(isize, isize) -> bool) -> Block {
+  let mut ghost = *block;
+  while ghost.try_move(MoveDirection::Down).cells().iter().all(|&(x, y)| free(x, y)) {
+    ghost = ghost.try_move(MoveDirection::Down);
+  }
+  ghost
+}
@@ -0,0 +1,700 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{notation, BlockType, Board, RotationState, Tetris, TetrisBuilder, GARBAGE};
+
+// fumen data is written with the digits of this table, least significant digit first
+const ENCODE_TABLE: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const COMMENT_TABLE: &[u8] = b" !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+const COMMENT_BASE: u32 = COMMENT_TABLE.len() as u32 + 1;
+
+const WIDTH: usize = 10;
+const HEIGHT: usize = 23; // rows in the field, the garbage row is below them
+const BLOCKS: usize = WIDTH * (HEIGHT + 1);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FumenError {
+  UnsupportedVersion,
+  InvalidCharacter(char),
+  UnexpectedEnd,
+  UnsupportedWidth(usize), // fumen fields are always 10 columns wide
+  PieceOutOfField(isize, isize), // x and y of a piece whose center is not in the field
+}
+
+impl fmt::Display for FumenError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Self::UnsupportedVersion => write!(f, "only v115 fumen data is supported"),
+      Self::InvalidCharacter(c) => write!(f, "invalid character '{}'", c),
+      Self::UnexpectedEnd => write!(f, "data ended in the middle of a page"),
+      Self::UnsupportedWidth(width) => write!(f, "playfield is {} columns wide instead of 10", width),
+      Self::PieceOutOfField(x, y) => write!(f, "piece at {}, {} is outside of the field", x, y),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for FumenError {}
+
+/**
+ * The piece on a page, x and y are the rotation center as used by fumen, with y counting up from the bottom row of the field
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FumenPiece {
+  pub block_type: BlockType,
+  pub rotation: RotationState,
+  pub x: isize,
+  pub y: isize,
+}
+
+impl FumenPiece {
+  fn offsets(block_type: BlockType, rotation: RotationState) -> [(isize, isize); 4] {
+    use BlockType::*;
+    let offsets = match block_type {
+      I => [(0, 0), (-1, 0), (1, 0), (2, 0)],
+      J => [(0, 0), (-1, 0), (1, 0), (-1, 1)],
+      L => [(0, 0), (-1, 0), (1, 0), (1, 1)],
+      O => [(0, 0), (1, 0), (0, 1), (1, 1)],
+      S => [(0, 0), (-1, 0), (0, 1), (1, 1)],
+      T => [(0, 0), (-1, 0), (1, 0), (0, 1)],
+      Z => [(0, 0), (1, 0), (0, 1), (-1, 1)],
+    };
+    let rotate: fn((isize, isize)) -> (isize, isize) = match rotation {
+      RotationState::Deg0 => |(x, y)| (x, y),
+      RotationState::Deg90 => |(x, y)| (y, -x),
+      RotationState::Deg180 => |(x, y)| (-x, -y),
+      RotationState::Deg270 => |(x, y)| (-y, x),
+    };
+    [rotate(offsets[0]), rotate(offsets[1]), rotate(offsets[2]), rotate(offsets[3])]
+  }
+
+  /**
+   * Positions of the cells in fumen coordinates
+   */
+  pub fn cells(&self) -> [(isize, isize); 4] {
+    let mut cells = Self::offsets(self.block_type, self.rotation);
+    cells.iter_mut().for_each(|cell| *cell = (self.x + cell.0, self.y + cell.1));
+    cells
+  }
+
+  /**
+   * Finds the piece that covers the same cells as the block, trying the rotation of the block first
+   */
+  fn from_cells(block_type: BlockType, rot_state: RotationState, mut cells: Vec<(isize, isize)>) -> Option<Self> {
+    use RotationState::*;
+    let normalize = |cells: &mut Vec<(isize, isize)>| {
+      cells.sort_unstable();
+      let (min_x, min_y) = (cells.iter().map(|c| c.0).min().unwrap(), cells.iter().map(|c| c.1).min().unwrap());
+      cells.iter_mut().for_each(|cell| *cell = (cell.0 - min_x, cell.1 - min_y));
+      (min_x, min_y)
+    };
+    let (min_x, min_y) = normalize(&mut cells);
+
+    [rot_state, Deg0, Deg90, Deg180, Deg270].iter().find_map(|&rotation| {
+      let mut offsets = Self::offsets(block_type, rotation).to_vec();
+      let (o_x, o_y) = normalize(&mut offsets);
+      if offsets == cells {
+        Some(Self { block_type, rotation, x: min_x - o_x, y: min_y - o_y })
+      } else {
+        None
+      }
+    })
+  }
+}
+
+/**
+ * The queue of a quiz comment, written as `#Q=[hold](current)next`
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quiz {
+  pub hold: Option<BlockType>,
+  pub current: Option<BlockType>,
+  pub next: Vec<BlockType>,
+}
+
+impl fmt::Display for Quiz {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let to_char = |block_type: Option<BlockType>| block_type.map(|b| notation::to_char(b as u32).to_string()).unwrap_or_default();
+    write!(f, "#Q=[{}]({})", to_char(self.hold), to_char(self.current))?;
+    self.next.iter().try_for_each(|&b| write!(f, "{}", notation::to_char(b as u32)))
+  }
+}
+
+impl FromStr for Quiz {
+  type Err = ();
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let block_type = |c: char| notation::from_char(c).and_then(BlockType::from_value).ok_or(());
+    let optional = |s: &str| s.chars().next().map(block_type).transpose();
+
+    let s = s.strip_prefix("#Q=[").ok_or(())?;
+    let (hold, s) = s.split_at(s.find(']').ok_or(())?);
+    let s = s.strip_prefix("](").ok_or(())?;
+    let (current, s) = s.split_at(s.find(')').ok_or(())?);
+    let next = s[1..]
+      .chars()
+      .take_while(|c| c.is_ascii_alphabetic())
+      .map(block_type)
+      .collect::<Result<_, _>>()?;
+
+    Ok(Self {
+      hold: optional(hold)?,
+      current: optional(current)?,
+      next,
+    })
+  }
+}
+
+/**
+ * One page of a fumen, the field and garbage row use playfield values
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct FumenPage {
+  pub field: Board,
+  pub garbage: Vec<u32>,
+  pub piece: Option<FumenPiece>,
+  pub comment: String,
+  pub lock: bool, // whether the piece is locked into the field of the next page and full lines are cleared
+  pub rise: bool, // whether the garbage row rises into the field of the next page
+  pub mirror: bool, // whether the field of the next page is mirrored
+}
+
+impl Default for FumenPage {
+  fn default() -> Self {
+    Self {
+      field: Board::new(WIDTH, HEIGHT),
+      garbage: vec![0; WIDTH],
+      piece: None,
+      comment: String::new(),
+      lock: true,
+      rise: false,
+      mirror: false,
+    }
+  }
+}
+
+impl FumenPage {
+  pub fn quiz(&self) -> Option<Quiz> {
+    self.comment.parse().ok()
+  }
+
+  /**
+   * The page that follows this one, with the piece locked if the flags say so and the same comment
+   */
+  pub fn next_page(&self) -> FumenPage {
+    let mut field = self.fumen_field();
+    next_field(&mut field, self);
+    FumenPage {
+      comment: self.comment.clone(),
+      ..FumenPage::from_fumen_field(&field)
+    }
+  }
+
+  // the field and garbage row as fumen values, in the order they are encoded
+  fn fumen_field(&self) -> [u32; BLOCKS] {
+    let mut field = [0; BLOCKS];
+    self
+      .field
+      .cells
+      .iter()
+      .chain(self.garbage.iter())
+      .zip(field.iter_mut())
+      .for_each(|(&val, cell)| *cell = to_fumen_value(val));
+    field
+  }
+
+  fn from_fumen_field(field: &[u32; BLOCKS]) -> FumenPage {
+    FumenPage {
+      field: Board {
+        width: WIDTH,
+        height: HEIGHT,
+        cells: field[..WIDTH * HEIGHT].iter().map(|&val| from_fumen_value(val)).collect(),
+      },
+      garbage: field[WIDTH * HEIGHT..].iter().map(|&val| from_fumen_value(val)).collect(),
+      ..FumenPage::default()
+    }
+  }
+
+  /**
+   * Decodes every page of v115 fumen data, the data can be a whole url
+   */
+  pub fn decode(data: &str) -> Result<Vec<FumenPage>, FumenError> {
+    let start = data.find("115@").ok_or(FumenError::UnsupportedVersion)? + 4;
+    let mut values = Values::decode(&data[start..])?;
+
+    let mut pages: Vec<FumenPage> = Vec::new();
+    let mut field = [0; BLOCKS];
+    let mut repeat = 0;
+    let mut comment = String::new();
+
+    while !values.is_empty() {
+      if repeat > 0 {
+        // the field did not change since the last page
+        repeat -= 1;
+      } else {
+        let mut index = 0;
+        let mut is_changed = true;
+        while index < BLOCKS {
+          let value = values.poll(2)?;
+          let (diff, count) = (value / BLOCKS as u32, value as usize % BLOCKS + 1);
+          if diff == 8 && count == BLOCKS {
+            is_changed = false;
+          }
+          for cell in field.iter_mut().skip(index).take(count) {
+            *cell = (*cell + diff).saturating_sub(8);
+          }
+          index += count;
+        }
+        if !is_changed {
+          repeat = values.poll(1)?;
+        }
+      }
+
+      let mut action = values.poll(3)?;
+      let piece_value = action % 8;
+      action /= 8;
+      let rotation = [RotationState::Deg180, RotationState::Deg90, RotationState::Deg0, RotationState::Deg270][action as usize % 4];
+      action /= 4;
+      let position = action as usize % BLOCKS;
+      action /= BLOCKS as u32;
+      let rise = action % 2 == 1;
+      let mirror = (action >> 1) % 2 == 1;
+      // the next bit tells whether guideline colors are used, which does not matter here
+      let has_comment = (action >> 3) % 2 == 1;
+      let lock = (action >> 4) % 2 == 0;
+
+      if has_comment {
+        let length = values.poll(2)? as usize;
+        let mut escaped = String::with_capacity(length + 3);
+        for _ in 0..length.div_ceil(4) {
+          let mut value = values.poll(5)?;
+          for _ in 0..4 {
+            let c = COMMENT_TABLE.get((value % COMMENT_BASE) as usize).ok_or(FumenError::InvalidCharacter('?'))?;
+            escaped.push(*c as char);
+            value /= COMMENT_BASE;
+          }
+        }
+        escaped.truncate(length);
+        comment = unescape(&escaped);
+      }
+
+      let piece = BlockType::from_value(from_fumen_value(piece_value)).map(|block_type| {
+        let (x, y) = decode_position(block_type, rotation, position);
+        FumenPiece { block_type, rotation, x, y }
+      });
+
+      pages.push(FumenPage {
+        piece,
+        comment: comment.clone(),
+        lock,
+        rise,
+        mirror,
+        ..FumenPage::from_fumen_field(&field)
+      });
+
+      next_field(&mut field, pages.last().unwrap());
+    }
+
+    Ok(pages)
+  }
+
+  /**
+   * Encodes the pages as v115 fumen data, each field is stored as the difference to the field of the previous page.
+   * Fails if the center of a piece is not in the field
+   */
+  pub fn encode(pages: &[FumenPage]) -> Result<String, FumenError> {
+    let mut values = Vec::new();
+    let mut prev_field = [0; BLOCKS];
+    let mut repeat_idx: Option<usize> = None;
+    let mut prev_comment = "";
+
+    for (i, page) in pages.iter().enumerate() {
+      let mut field = page.fumen_field();
+
+      // runs of cells with the same difference, a field without any difference is only written again after 64 repeats
+      let mut field_values = Vec::new();
+      let mut runs = field.iter().zip(prev_field.iter()).map(|(&cell, &prev)| cell + 8 - prev);
+      let mut run = (runs.next().unwrap(), 0);
+      for diff in runs {
+        if diff == run.0 {
+          run.1 += 1;
+        } else {
+          push(&mut field_values, run.0 * BLOCKS as u32 + run.1, 2);
+          run = (diff, 0);
+        }
+      }
+      push(&mut field_values, run.0 * BLOCKS as u32 + run.1, 2);
+
+      if run != (8, BLOCKS as u32 - 1) {
+        values.extend(field_values);
+        repeat_idx = None;
+      } else {
+        match repeat_idx {
+          Some(idx) if values[idx] < ENCODE_TABLE.len() as u32 - 1 => values[idx] += 1,
+          _ => {
+            values.extend(field_values);
+            values.push(0);
+            repeat_idx = Some(values.len() - 1);
+          }
+        }
+      }
+
+      let has_comment = page.comment != prev_comment;
+      let (piece_value, rotation, position) = match page.piece {
+        Some(piece) => (to_fumen_value(piece.block_type as u32), piece.rotation, encode_position(&piece)?),
+        None => (0, RotationState::Deg180, 0),
+      };
+      let rotation = match rotation {
+        RotationState::Deg180 => 0,
+        RotationState::Deg90 => 1,
+        RotationState::Deg0 => 2,
+        RotationState::Deg270 => 3,
+      };
+      let flags = (!page.lock as u32) << 4 | (has_comment as u32) << 3 | ((i == 0) as u32) << 2 | (page.mirror as u32) << 1 | page.rise as u32;
+      push(&mut values, ((flags * BLOCKS as u32 + position) * 4 + rotation) * 8 + piece_value, 3);
+
+      if has_comment {
+        let escaped = escape(&page.comment);
+        let escaped = &escaped.as_bytes()[..escaped.len().min(4095)];
+        push(&mut values, escaped.len() as u32, 2);
+        for chunk in escaped.chunks(4) {
+          let value = chunk
+            .iter()
+            .rev()
+            .fold(0, |acc, c| acc * COMMENT_BASE + COMMENT_TABLE.iter().position(|t| t == c).unwrap() as u32);
+          push(&mut values, value, 5);
+        }
+        prev_comment = &page.comment;
+      }
+
+      next_field(&mut field, page);
+      prev_field = field;
+    }
+
+    let data: String = values.iter().map(|&v| ENCODE_TABLE[v as usize] as char).collect();
+
+    // fumen breaks long data with question marks, after the first 42 characters and then every 47
+    let (head, tail) = data.as_bytes().split_at(data.len().min(42));
+    let mut out = String::from("v115@");
+    out.push_str(core::str::from_utf8(head).unwrap());
+    tail.chunks(47).for_each(|chunk| {
+      out.push('?');
+      out.push_str(core::str::from_utf8(chunk).unwrap());
+    });
+    Ok(out)
+  }
+}
+
+struct Values {
+  values: Vec<u32>,
+  pos: usize,
+}
+
+impl Values {
+  fn decode(data: &str) -> Result<Self, FumenError> {
+    let values = data
+      .chars()
+      .filter(|&c| c != '?')
+      .map(|c| ENCODE_TABLE.iter().position(|&t| t as char == c).map(|v| v as u32).ok_or(FumenError::InvalidCharacter(c)))
+      .collect::<Result<_, _>>()?;
+    Ok(Self { values, pos: 0 })
+  }
+
+  fn is_empty(&self) -> bool {
+    self.pos >= self.values.len()
+  }
+
+  fn poll(&mut self, digits: usize) -> Result<u32, FumenError> {
+    let digits = self.values.get(self.pos..self.pos + digits).ok_or(FumenError::UnexpectedEnd)?;
+    self.pos += digits.len();
+    Ok(digits.iter().rev().fold(0, |acc, &v| acc * ENCODE_TABLE.len() as u32 + v))
+  }
+}
+
+fn push(values: &mut Vec<u32>, mut value: u32, digits: usize) {
+  for _ in 0..digits {
+    values.push(value % ENCODE_TABLE.len() as u32);
+    value /= ENCODE_TABLE.len() as u32;
+  }
+}
+
+// fumen numbers blocks as I, L, O, Z, T, J, S and gray
+fn to_fumen_value(val: u32) -> u32 {
+  [0, 1, 6, 2, 3, 7, 5, 4, 8].get(val as usize).copied().unwrap_or(8)
+}
+
+fn from_fumen_value(val: u32) -> u32 {
+  [0, 1, 3, 4, 7, 6, 2, 5, GARBAGE].get(val as usize).copied().unwrap_or(GARBAGE)
+}
+
+// fumen stores some pieces by a different center than the one used for their cells
+fn position_shift(block_type: BlockType, rotation: RotationState) -> (isize, isize) {
+  use BlockType::*;
+  use RotationState::*;
+  match (block_type, rotation) {
+    (O, Deg270) => (1, -1),
+    (O, Deg180) | (I, Deg180) => (1, 0),
+    (O, Deg0) | (I, Deg270) | (S, Deg0) | (Z, Deg0) => (0, -1),
+    (S, Deg90) => (-1, 0),
+    (Z, Deg270) => (1, 0),
+    _ => (0, 0),
+  }
+}
+
+// the garbage row below the field is at -1
+fn encode_position(piece: &FumenPiece) -> Result<u32, FumenError> {
+  let (d_x, d_y) = position_shift(piece.block_type, piece.rotation);
+  let x = piece.x.checked_add(d_x).filter(|x| (0..WIDTH as isize).contains(x));
+  let y = piece.y.checked_add(d_y).filter(|y| (-1..HEIGHT as isize).contains(y));
+  match (x, y) {
+    (Some(x), Some(y)) => Ok(((HEIGHT as isize - 1 - y) * WIDTH as isize + x) as u32),
+    _ => Err(FumenError::PieceOutOfField(piece.x, piece.y)),
+  }
+}
+
+fn decode_position(block_type: BlockType, rotation: RotationState, position: usize) -> (isize, isize) {
+  let (d_x, d_y) = position_shift(block_type, rotation);
+  let (x, y) = ((position % WIDTH) as isize, HEIGHT as isize - 1 - (position / WIDTH) as isize);
+  (x - d_x, y - d_y)
+}
+
+/**
+ * Applies the flags of the page to get the field of the next page
+ */
+fn next_field(field: &mut [u32; BLOCKS], page: &FumenPage) {
+  if !page.lock {
+    return;
+  }
+
+  if let Some(piece) = page.piece {
+    for &(x, y) in piece.cells().iter() {
+      let row = HEIGHT as isize - 1 - y;
+      if (0..WIDTH as isize).contains(&x) && (0..HEIGHT as isize).contains(&row) {
+        field[row as usize * WIDTH + x as usize] = to_fumen_value(piece.block_type as u32);
+      }
+    }
+  }
+
+  // full rows are removed and the field above falls, the garbage row is not part of the field
+  let mut rows: Vec<[u32; WIDTH]> = field[..WIDTH * HEIGHT]
+    .chunks_exact(WIDTH)
+    .filter(|row| row.contains(&0))
+    .map(|row| {
+      let mut copy = [0; WIDTH];
+      copy.copy_from_slice(row);
+      copy
+    })
+    .collect();
+
+  if page.rise {
+    let mut garbage = [0; WIDTH];
+    garbage.copy_from_slice(&field[WIDTH * HEIGHT..]);
+    rows.push(garbage);
+    field[WIDTH * HEIGHT..].iter_mut().for_each(|cell| *cell = 0);
+  }
+
+  if page.mirror {
+    rows.iter_mut().for_each(|row| row.reverse());
+  }
+
+  let rows = &rows[rows.len().saturating_sub(HEIGHT)..];
+  let empty = HEIGHT - rows.len();
+  field[..WIDTH * empty].iter_mut().for_each(|cell| *cell = 0);
+  rows.iter().enumerate().for_each(|(i, row)| field[(empty + i) * WIDTH..(empty + i + 1) * WIDTH].copy_from_slice(row));
+}
+
+// comments are stored with the escaping of javascript's escape()
+fn escape(s: &str) -> String {
+  let mut out = String::new();
+  for c in s.chars() {
+    if c.is_ascii_alphanumeric() || "@*_+-./".contains(c) {
+      out.push(c);
+    } else if (c as u32) < 256 {
+      out.push_str(&format!("%{:02X}", c as u32));
+    } else {
+      let mut buf = [0; 2];
+      c.encode_utf16(&mut buf).iter().for_each(|unit| out.push_str(&format!("%u{:04X}", unit)));
+    }
+  }
+  out
+}
+
+fn unescape(s: &str) -> String {
+  let mut units: Vec<u16> = Vec::with_capacity(s.len());
+  let mut rest = s;
+  while let Some(c) = rest.chars().next() {
+    let hex = |digits: &str| u16::from_str_radix(digits, 16).ok();
+    let (unit, len) = match (rest.get(1..2), rest.get(2..6), rest.get(1..3)) {
+      (Some("u"), Some(digits), _) if c == '%' && hex(digits).is_some() => (hex(digits).unwrap(), 6),
+      (_, _, Some(digits)) if c == '%' && hex(digits).is_some() => (hex(digits).unwrap(), 3),
+      _ => (c as u16, c.len_utf8()),
+    };
+    units.push(unit);
+    rest = &rest[len..];
+  }
+  String::from_utf16_lossy(&units)
+}
+
+impl Tetris {
+  /**
+   * The playfield aligned to the bottom of the fumen field, with the current block as the piece and the queue in a quiz comment
+   */
+  pub fn fumen_page(&self) -> Result<FumenPage, FumenError> {
+    if self.dim.width != WIDTH {
+      return Err(FumenError::UnsupportedWidth(self.dim.width));
+    }
+
+    let mut page = FumenPage::default();
+    let playfield = self.playfield.borrow();
+    for row in 0..self.dim.height.min(HEIGHT) {
+      let (src, dest) = (self.dim.height - 1 - row, HEIGHT - 1 - row);
+      page.field.cells[dest * WIDTH..(dest + 1) * WIDTH].copy_from_slice(&playfield[src * WIDTH..(src + 1) * WIDTH]);
+    }
+
+    let block = &self.curr_block;
+    let cells = block.cells().iter().map(|&(x, y)| (x, self.dim.height as isize - 1 - y)).collect();
+    page.piece = FumenPiece::from_cells(block.block_type, block.rot_state, cells).filter(|piece| piece.y < HEIGHT as isize);
+
+    page.comment = Quiz {
+      hold: self.hold,
+      current: Some(block.block_type),
+      next: self.queue.iter().copied().collect(),
+    }.to_string();
+
+    Ok(page)
+  }
+
+  pub fn to_fumen(&self) -> Result<String, FumenError> {
+    FumenPage::encode(&[self.fumen_page()?])
+  }
+}
+
+impl TetrisBuilder {
+  /**
+   * Starts from the field of the page aligned to the bottom of the playfield, the queue and hold are taken from its quiz comment,
+   * or the piece of the page is used as the first block
+   */
+  pub fn with_fumen_page(mut self, page: &FumenPage) -> Self {
+    let mut board = Board::new(WIDTH, self.height);
+    for row in 0..self.height.min(HEIGHT) {
+      let (src, dest) = (HEIGHT - 1 - row, self.height - 1 - row);
+      board.cells[dest * WIDTH..(dest + 1) * WIDTH].copy_from_slice(&page.field.cells[src * WIDTH..(src + 1) * WIDTH]);
+    }
+    self = self.with_board(board);
+
+    match page.quiz() {
+      Some(quiz) => self
+        .with_hold(quiz.hold)
+        .with_queue(quiz.current.into_iter().chain(quiz.next).collect()),
+      None => self.with_queue(page.piece.map(|piece| piece.block_type).into_iter().collect()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::TetrisBuilder;
+
+  fn t_page() -> FumenPage {
+    FumenPage {
+      piece: Some(FumenPiece { block_type: BlockType::T, rotation: RotationState::Deg0, x: 4, y: 0 }),
+      ..FumenPage::default()
+    }
+  }
+
+  #[test]
+  fn decodes_empty_field() {
+    let pages = FumenPage::decode("v115@vhAAgH").unwrap();
+    assert_eq!(pages, vec![FumenPage::default()]);
+  }
+
+  #[test]
+  fn encodes_piece() {
+    assert_eq!(FumenPage::encode(&[t_page()]), Ok(String::from("v115@vhAVQJ")));
+    assert_eq!(FumenPage::decode("v115@vhAVQJ").unwrap(), vec![t_page()]);
+
+    for &(x, y) in [(10, 0), (-1, 0), (4, 23), (4, isize::MAX)].iter() {
+      let page = FumenPage { piece: Some(FumenPiece { x, y, ..t_page().piece.unwrap() }), ..t_page() };
+      assert_eq!(FumenPage::encode(&[page]), Err(FumenError::PieceOutOfField(x, y)));
+    }
+  }
+
+  #[test]
+  fn locks_pieces_into_the_next_page() {
+    let mut first = t_page();
+    first.field.cells[22 * WIDTH..].copy_from_slice(&[3, 3, 3, 0, 0, 0, 5, 5, 5, 5]);
+    first.comment = String::from("T-spin? こんにちは");
+    let second = FumenPage {
+      piece: Some(FumenPiece { block_type: BlockType::I, rotation: RotationState::Deg90, x: 0, y: 2 }),
+      ..first.next_page()
+    };
+    let third = second.next_page();
+
+    // the T filled the bottom row and the row was cleared, only the tip of the T is left
+    let mut field = Board::new(WIDTH, HEIGHT);
+    field.set(4, 22, BlockType::T as u32);
+    assert_eq!(second.field, field);
+    assert_eq!(third.field.get(0, 19), BlockType::I as u32);
+
+    let pages = FumenPage::decode(&FumenPage::encode(&[first.clone(), second.clone(), third.clone()]).unwrap()).unwrap();
+    assert_eq!(pages, vec![first, second, third]);
+  }
+
+  #[test]
+  fn breaks_long_data() {
+    let pages: Vec<_> = (0..20).map(|i| FumenPage { comment: i.to_string(), ..t_page() }).collect();
+    let data = FumenPage::encode(&pages).unwrap();
+    assert_eq!(data.find('?'), Some(5 + 42));
+    assert_eq!(FumenPage::decode(&format!("https://fumen.zui.jp/?{}", data)).unwrap()[19].comment, "19");
+  }
+
+  #[test]
+  fn keeps_data_of_up_to_42_characters_whole() {
+    // every cell that differs from the one before starts a run, which takes two characters
+    let mut short = FumenPage::default();
+    (1..18).step_by(2).for_each(|i| short.field.cells[i] = GARBAGE);
+    let mut long = FumenPage { comment: String::from("a"), ..FumenPage::default() };
+    (0..16).step_by(2).for_each(|i| long.field.cells[i] = GARBAGE);
+
+    for (page, len) in [(short, 41), (long, 42)] {
+      let data = FumenPage::encode(core::slice::from_ref(&page)).unwrap();
+      assert_eq!(data.len(), 5 + len);
+      assert!(!data.contains('?'));
+      assert_eq!(FumenPage::decode(&data).unwrap(), vec![page]);
+    }
+  }
+
+  #[test]
+  fn round_trips_tetris() {
+    let page = FumenPage {
+      comment: String::from("#Q=[Z](T)OSI"),
+      ..FumenPage::decode("v115@vhAAgH").unwrap().remove(0)
+    };
+    let mut page = page;
+    page.field.cells[22 * WIDTH..].copy_from_slice(&[8, 8, 8, 8, 0, 8, 8, 8, 8, 8]);
+
+    let tetris = TetrisBuilder::new(10, 20)
+      .with_preview(3)
+      .with_fumen_page(&page)
+      .build();
+
+    assert_eq!(tetris.hold(), Some(BlockType::Z));
+    assert_eq!(tetris.curr_block.block_type, BlockType::T);
+    assert_eq!(tetris.board().to_string().lines().last(), Some("GGGGxGGGGG"));
+
+    let exported = tetris.fumen_page().unwrap();
+    assert_eq!(exported.field, page.field);
+    assert_eq!(exported.quiz().unwrap(), Quiz {
+      hold: Some(BlockType::Z),
+      current: Some(BlockType::T),
+      next: vec![BlockType::O, BlockType::S, BlockType::I],
+    });
+    // the classic T spawns pointing down, which fumen calls the reverse orientation
+    assert_eq!(exported.piece, Some(FumenPiece { block_type: BlockType::T, rotation: RotationState::Deg180, x: 4, y: 18 }));
+  }
+}
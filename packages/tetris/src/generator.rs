@@ -0,0 +1,146 @@
+use alloc::vec::Vec;
+
+use crate::{BlockType, Randomizer};
+
+/**
+ * Picks the type of each new block, using the randomizer as the source of randomness
+ */
+pub trait PieceGenerator {
+  fn next(&mut self, rng: &mut dyn Randomizer<u32>) -> BlockType;
+}
+
+/**
+ * Every block type is equally likely every time
+ */
+pub struct Uniform;
+
+impl PieceGenerator for Uniform {
+  fn next(&mut self, rng: &mut dyn Randomizer<u32>) -> BlockType {
+    BlockType::get_random(rng)
+  }
+}
+
+/**
+ * Deals all seven block types in a random order before starting over
+ */
+pub struct SevenBag {
+  bag: Vec<BlockType>,
+}
+
+impl SevenBag {
+  pub fn new() -> Self {
+    Self { bag: Vec::with_capacity(7) }
+  }
+}
+
+impl Default for SevenBag {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PieceGenerator for SevenBag {
+  fn next(&mut self, rng: &mut dyn Randomizer<u32>) -> BlockType {
+    if self.bag.is_empty() {
+      self.bag.extend_from_slice(&BlockType::ALL);
+      // Fisher-Yates shuffle, blocks are dealt from the back
+      for i in (1..self.bag.len()).rev() {
+        let j = rng.get_random() as usize % (i + 1);
+        self.bag.swap(i, j);
+      }
+    }
+    self.bag.pop().unwrap()
+  }
+}
+
+/**
+ * The NES algorithm, rolls one of eight outcomes and rolls again once if it got the extra outcome or a repeat of the last block
+ */
+pub struct NesReroll {
+  last: Option<BlockType>,
+}
+
+impl NesReroll {
+  pub fn new() -> Self {
+    Self { last: None }
+  }
+}
+
+impl Default for NesReroll {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+impl PieceGenerator for NesReroll {
+  fn next(&mut self, rng: &mut dyn Randomizer<u32>) -> BlockType {
+    let roll = rng.get_random() % 8;
+    let block_type = match BlockType::ALL.get(roll as usize) {
+      Some(&block_type) if Some(block_type) != self.last => block_type,
+      // the second roll is kept even if it repeats
+      _ => BlockType::get_random(rng),
+    };
+    self.last = Some(block_type);
+    block_type
+  }
+}
+
+/**
+ * The TGM algorithm, rerolls blocks that are in the history of the last four blocks up to a number of tries,
+ * the first block is never an S, Z or O block
+ */
+pub struct TgmHistory {
+  history: [BlockType; 4],
+  tries: u32,
+  is_first: bool,
+}
+
+impl TgmHistory {
+  /**
+   * The history starts with four Z blocks and blocks are rolled up to four times
+   */
+  pub fn tgm1() -> Self {
+    use BlockType::*;
+    Self::new([Z, Z, Z, Z], 4)
+  }
+
+  /**
+   * The history starts with Z, S, S, Z and blocks are rolled up to six times
+   */
+  pub fn tgm2() -> Self {
+    use BlockType::*;
+    Self::new([Z, S, S, Z], 6)
+  }
+
+  pub fn new(history: [BlockType; 4], tries: u32) -> Self {
+    Self {
+      history,
+      tries,
+      is_first: true,
+    }
+  }
+}
+
+impl PieceGenerator for TgmHistory {
+  fn next(&mut self, rng: &mut dyn Randomizer<u32>) -> BlockType {
+    let block_type = if self.is_first {
+      use BlockType::*;
+      self.is_first = false;
+      [I, J, L, T][rng.get_random() as usize % 4]
+    } else {
+      let mut block_type = BlockType::get_random(rng);
+      // the last try is kept even if it is in the history
+      for _ in 1..self.tries {
+        if !self.history.contains(&block_type) {
+          break;
+        }
+        block_type = BlockType::get_random(rng);
+      }
+      block_type
+    };
+
+    self.history.rotate_left(1);
+    self.history[3] = block_type;
+    block_type
+  }
+}
@@ -0,0 +1,178 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use crate::{BlockType, Phase, Stats};
+
+/**
+ * Everything besides the playfield that changes when a block locks
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct State {
+  pub block_type: BlockType, // type of the current block, it is respawned at the top when the state is restored
+  pub hold: Option<BlockType>,
+  pub hold_used: bool,
+  pub combo: Option<u32>,
+  pub back_to_back: bool,
+  pub score: u64,
+  pub lines: u32,
+  pub level: u32,
+  pub stats: Stats,
+  pub phase: Phase, // only kept to know whether the game was over
+  pub queue_len: usize,
+  pub dealt: usize, // number of dealt blocks that have entered the queue, the queue is the last `queue_len` of them
+}
+
+/**
+ * One lock, only the playfield cells that changed are kept
+ */
+struct Record {
+  changes: Vec<(u32, u32, u32)>, // index, value before the lock, value after the lock
+  before: State,
+  after: State,
+}
+
+/**
+ * Undo and redo stacks for practice mode.
+ * Blocks taken from the piece generator are logged, so after an undo the same blocks are dealt again
+ * without having to restore the randomizer or the generator.
+ */
+pub(crate) struct History {
+  dealt: VecDeque<BlockType>, // only the blocks that a lock which can be undone or redone may need again
+  forgotten: usize, // number of blocks dealt before the first in `dealt`
+  cursor: usize,
+  undo: VecDeque<Record>,
+  redo: Vec<Record>,
+  limit: usize, // maximum number of locks that can be undone, the oldest are forgotten first
+}
+
+impl History {
+  /**
+   * The initial queue counts as dealt so it can be restored like the rest
+   */
+  pub fn new(limit: usize, queue: &VecDeque<BlockType>) -> Self {
+    Self {
+      dealt: queue.clone(),
+      forgotten: 0,
+      cursor: queue.len(),
+      undo: VecDeque::new(),
+      redo: Vec::new(),
+      limit,
+    }
+  }
+
+  /**
+   * Block that was already dealt once at this point, if the game went back in time
+   */
+  pub fn replayed(&self) -> Option<BlockType> {
+    self.dealt.get(self.cursor - self.forgotten).copied()
+  }
+
+  pub fn deal(&mut self, block_type: BlockType) {
+    if self.cursor == self.forgotten + self.dealt.len() {
+      self.dealt.push_back(block_type);
+    }
+    self.cursor += 1;
+  }
+
+  /**
+   * Forgets every lock, the blocks that were dealt are kept so the queue stays the same
+   */
+  pub fn clear(&mut self) {
+    self.undo.clear();
+    self.redo.clear();
+  }
+
+  /**
+   * Forgets every lock and every dealt block, the queue counts as dealt like in `new`
+   */
+  pub fn reset(&mut self, queue: &VecDeque<BlockType>) {
+    *self = Self::new(self.limit, queue);
+  }
+
+  pub fn cursor(&self) -> usize {
+    self.cursor
+  }
+
+  /**
+   * Blocks of the queue that was saved with the state
+   */
+  pub fn queue(&self, state: &State) -> VecDeque<BlockType> {
+    self.dealt.range(state.dealt - state.queue_len - self.forgotten..state.dealt - self.forgotten).copied().collect()
+  }
+
+  /**
+   * Saves a lock, anything that was undone can no longer be redone
+   */
+  pub fn push(&mut self, playfield_before: &[u32], playfield_after: &[u32], before: State, after: State) {
+    let changes = playfield_before
+      .iter()
+      .zip(playfield_after.iter())
+      .enumerate()
+      .filter(|(_, (old, new))| old != new)
+      .map(|(i, (&old, &new))| (i as u32, old, new))
+      .collect();
+
+    self.redo.clear();
+    self.undo.push_back(Record { changes, before, after });
+    if self.undo.len() > self.limit {
+      self.undo.pop_front();
+    }
+
+    // blocks that came before the queue of the oldest lock can not be dealt again
+    let oldest = self.undo.front().map_or(after, |record| record.before);
+    let keep = oldest.dealt - oldest.queue_len;
+    self.dealt.drain(..keep - self.forgotten);
+    self.forgotten = keep;
+  }
+
+  /**
+   * Reverts the playfield to before the last lock and returns the state to restore
+   */
+  pub fn undo(&mut self, playfield: &mut [u32]) -> Option<State> {
+    let record = self.undo.pop_back()?;
+    record.changes.iter().for_each(|&(i, old, _)| playfield[i as usize] = old);
+    self.cursor = record.before.dealt;
+    let state = record.before;
+    self.redo.push(record);
+    Some(state)
+  }
+
+  /**
+   * Applies the last undone lock again and returns the state to restore
+   */
+  pub fn redo(&mut self, playfield: &mut [u32]) -> Option<State> {
+    let record = self.redo.pop()?;
+    record.changes.iter().for_each(|&(i, _, new)| playfield[i as usize] = new);
+    self.cursor = record.after.dealt;
+    let state = record.after;
+    self.undo.push_back(record);
+    Some(state)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{MoveDirection, TetrisBuilder};
+
+  #[test]
+  fn forgets_blocks_that_can_not_be_dealt_again() {
+    let mut tetris = TetrisBuilder::new(10, 20).with_seed(3).with_history(2).build();
+    for _ in 0..6 {
+      while tetris.move_obj(MoveDirection::Down).is_ok() {}
+      tetris.update();
+    }
+    let board = tetris.board();
+    let queue = tetris.queue.clone();
+
+    // the queue two locks back and the two blocks dealt since
+    let history = tetris.history.as_ref().unwrap();
+    assert_eq!(history.forgotten, 5);
+    assert_eq!(history.dealt.len(), queue.len() + 2);
+
+    assert!(tetris.undo() && tetris.undo());
+    assert!(!tetris.undo());
+    assert!(tetris.redo() && tetris.redo());
+    assert_eq!(tetris.board(), board);
+    assert_eq!(tetris.queue, queue);
+  }
+}
@@ -1,9 +1,52 @@
-use std::cell::RefCell;
+#![cfg_attr(not(feature = "std"), no_std)]
 
-use nalgebra as na;
-use na::{Matrix3x4, Matrix3};
+extern crate alloc;
 
-#[derive(Copy, Clone)]
+mod clear;
+mod coop;
+mod dig;
+mod editor;
+mod finesse;
+mod frame;
+mod fumen;
+mod generator;
+mod history;
+mod modifiers;
+mod notation;
+mod prng;
+mod replay;
+mod rotation;
+mod speed;
+mod stats;
+mod zobrist;
+
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+use coop::Player;
+use dig::DigState;
+use history::{History, State};
+use zobrist::Zobrist;
+
+pub use clear::{LineClear, TSpin};
+pub use dig::{Dig, Rise};
+pub use editor::{EditError, Puzzle, ParsePuzzleError};
+pub use finesse::FinesseFault;
+pub use frame::{Frame, Cell, CellKind, PartnerFrame, Status};
+pub use fumen::{FumenPage, FumenPiece, FumenError, Quiz};
+pub use modifiers::Modifiers;
+pub use generator::{PieceGenerator, Uniform, SevenBag, NesReroll, TgmHistory};
+pub use notation::{to_char, Board, ParseActionError, ParseBoardError};
+pub use prng::Pcg32;
+pub use replay::{Replay, ParseReplayError};
+pub use rotation::{RotationSystem, ClassicRotation, Srs, Ars, Nrs};
+pub use speed::{Speed, SpeedTable, LevelUp, G, GRAVITY_20G};
+pub use stats::{Stats, FRAMES_PER_SECOND};
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum RotationState {
   Deg0,
   Deg90,
@@ -23,30 +66,76 @@ impl RotationState {
   }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MoveDirection {
   Down,
   Left,
   Right,
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum RotationDirection {
   Clockwise,
   CounterClockwise,
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum TetrisAction {
   Move(MoveDirection),
   Rotate(RotationDirection),
-  // TODO drop
+  Hold,
+  HardDrop, // moves the block down as far as it goes and locks it
+  Press(MoveDirection), // moves the block like `Move` and keeps moving it while the direction is held, see `Speed::das`
+  Release, // lets go of the held direction
+}
+
+/**
+ * Where the game is between spawning a block and spawning the next one, advanced by `Tetris::tick`
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Phase {
+  Falling, // the current block is in the air and falls with gravity
+  Locking, // the current block rests on the stack and locks once the lock delay runs out
+  LineClearAnimating, // the cleared lines stay on the playfield for the line clear delay
+  Entry, // waiting for the entry delay to spawn the next block
+  GameOver, // the playfield topped out, nothing happens until `Tetris::restart`
+  Finished, // the goal of the mode was reached, nothing happens until `Tetris::restart`
 }
 
+impl Phase {
+  pub fn is_over(&self) -> bool {
+    matches!(self, Phase::GameOver | Phase::Finished)
+  }
+
+  /**
+   * Whether the current block is in play, in the other phases it is the block that last locked
+   */
+  pub fn has_block(&self) -> bool {
+    matches!(self, Phase::Falling | Phase::Locking)
+  }
+}
+
+/**
+ * Things that happened during an update, drained with `Tetris::drain_events`
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TetrisEvent {
+  LineClear(LineClear),
+  GameOver(Stats), // the playfield topped out, nothing happens until `Tetris::restart`
+  Finished(Stats), // every garbage row of a dig was cleared, the stats hold the finish time and the pieces used
+  FinesseFault(FinesseFault),
+}
+
+/**
+ * Playfield value of cells that do not belong to any block type
+ */
+pub const GARBAGE: u32 = 8;
+
 pub trait Randomizer<T> {
   fn get_random(&mut self) -> T;
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum BlockType {
   I = 1,
   J,
@@ -58,40 +147,25 @@ pub enum BlockType {
 }
 
 impl BlockType {
-  fn get_inital(&self) -> (Matrix3x4<isize>, usize) { // the last value is the ith column in the matrix, pivot index
-    // blocks are initially shifted 3 to the right TODO: base the offset on width instead of hardcoding
-    match *self {
-      Self::I => (Matrix3x4::new(3, 4, 5, 6,
-                                 2, 2, 2, 2,
-                                 1, 1, 1, 1), 2),
-
-      Self::J => (Matrix3x4::new(3, 4, 5, 5,
-                                 1, 1, 1, 2,
-                                 1, 1, 1, 1), 1),
+  pub const ALL: [BlockType; 7] = [Self::I, Self::J, Self::L, Self::O, Self::S, Self::T, Self::Z];
 
-      Self::L => (Matrix3x4::new(3, 4, 5, 5,
-                                 1, 1, 1, 0,
-                                 1, 1, 1, 1), 1),
-
-      Self::O => (Matrix3x4::new(4, 4, 5, 5,
-                                 1, 2, 1, 2,
-                                 1, 1, 1, 1), 0),
-
-      Self::S => (Matrix3x4::new(4, 5, 3, 4,
-                                 1, 1, 2, 2,
-                                 1, 1, 1, 1), 0),
-
-      Self::T => (Matrix3x4::new(3, 4, 4, 5,
-                                 1, 1, 2, 1,
-                                 1, 1, 1, 1), 1),
-
-      Self::Z => (Matrix3x4::new(3, 4, 4, 5,
-                                 1, 1, 2, 2,
-                                 1, 1, 1, 1), 1),
+  // where the block spawns with the classic rotation on a playfield of width 10, the origin is the pivot cell
+  // TODO: base the spawn column on width instead of hardcoding
+  fn get_inital(&self) -> (isize, isize) {
+    match *self {
+      Self::I => (5, 2),
+      _ => (4, 1),
     }
   }
 
-  fn get_random(rng: &mut Box<dyn Randomizer<u32>>) -> Self {
+  /**
+   * Block type of a playfield value, None for empty cells and garbage
+   */
+  pub fn from_value(val: u32) -> Option<Self> {
+    Self::ALL.get((val as usize).wrapping_sub(1)).copied()
+  }
+
+  fn get_random(rng: &mut dyn Randomizer<u32>) -> Self {
     match rng.get_random() % 7 {
       0 => Self::I,
       1 => Self::J,
@@ -104,96 +178,107 @@ impl BlockType {
   }
 }
 
+/**
+ * Cells of a block relative to its origin
+ */
+pub type Offsets = [(i8, i8); 4];
+
+// turns the offsets a quarter clockwise about the origin, y points down
+const fn rotate_offsets(offsets: Offsets) -> Offsets {
+  let mut rotated = offsets;
+  let mut i = 0;
+  while i < 4 {
+    rotated[i] = (-offsets[i].1, offsets[i].0);
+    i += 1;
+  }
+  rotated
+}
+
+// the offsets of every rotation state, in the order of Deg0, Deg90, Deg180 and Deg270
+const fn rotations(spawn: Offsets) -> [Offsets; 4] {
+  let deg_90 = rotate_offsets(spawn);
+  let deg_180 = rotate_offsets(deg_90);
+  [spawn, deg_90, deg_180, rotate_offsets(deg_180)]
+}
+
+// classic rotation tables, blocks rotate about the cell at the origin
+const CLASSIC_OFFSETS: [[Offsets; 4]; 7] = [
+  rotations([(-2, 0), (-1, 0), (0, 0), (1, 0)]), // I
+  rotations([(-1, 0), (0, 0), (1, 0), (1, 1)]), // J
+  rotations([(-1, 0), (0, 0), (1, 0), (1, -1)]), // L
+  rotations([(0, 0), (0, 1), (1, 0), (1, 1)]), // O
+  rotations([(0, 0), (1, 0), (-1, 1), (0, 1)]), // S
+  rotations([(-1, 0), (0, 0), (0, 1), (1, 0)]), // T
+  rotations([(-1, 0), (0, 0), (0, 1), (1, 1)]), // Z
+];
+
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Block {
   pub rot_state: RotationState,
   pub block_type: BlockType,
-  pub pos: Matrix3x4<isize>,
-  pub pivot_idx: usize,
+  pub origin: (isize, isize),
+  pub offsets: Offsets,
 }
 
 impl Block {
   pub fn new(block_type: BlockType) -> Self {
+    Self::from_shape(block_type, RotationState::Deg0, block_type.get_inital(), Self::classic_offsets(block_type, RotationState::Deg0))
+  }
 
-    let (pos, pivot_idx) = block_type.get_inital();
-
-    let rot_state = RotationState::Deg0;
-
+  /**
+   * Creates a block from offsets given by a rotation system, `try_rotate` assumes the classic offsets
+   */
+  pub fn from_shape(block_type: BlockType, rot_state: RotationState, origin: (isize, isize), offsets: Offsets) -> Self {
     Self {
-      pos,
-      pivot_idx,
-      block_type,
       rot_state,
+      block_type,
+      origin,
+      offsets,
     }
   }
 
-  pub fn try_rotate(&self, mut dir: RotationDirection) -> (Matrix3x4<isize>, RotationState) {
-    let current_pos = self.pos.clone();
+  fn classic_offsets(block_type: BlockType, rot_state: RotationState) -> Offsets {
+    CLASSIC_OFFSETS[block_type as usize - 1][rot_state as usize]
+  }
 
+  /**
+   * Playfield positions of the four cells
+   */
+  pub fn cells(&self) -> [(isize, isize); 4] {
+    let (o_x, o_y) = self.origin;
+    let mut cells = [(0, 0); 4];
+    for (cell, &(x, y)) in cells.iter_mut().zip(self.offsets.iter()) {
+      *cell = (o_x + x as isize, o_y + y as isize);
+    }
+    cells
+  }
+
+  pub fn try_rotate(&self, dir: RotationDirection) -> Self {
     use BlockType::*;
     match self.block_type {
-      O => (current_pos, self.rot_state),
+      O => *self,
       _ => {
-        let mut next_rot_state = self.rot_state;
-        
         use RotationState::*;
-        match (self.block_type, self.rot_state) {
+        let rot_state = match (self.block_type, self.rot_state) {
           // I block has only two states, 0 and 90
-          // if 0, rotate clockwise
-          // if 90, rotate cclockwise
-          (I, Deg0) => {
-            next_rot_state = Deg90;
-            dir = Clockwise;
-          },
-          (I, Deg90) => {
-            next_rot_state = Deg0;
-            dir = CounterClockwise;
-          },
-          (J, _) | (L, _) | (T, _) => {
-            next_rot_state = self.rot_state.get_next_state(dir);
-          },
+          (I, Deg0) => Deg90,
+          (I, Deg90) => Deg0,
           // S and z blocks have only two states, 0 and 270
-          // if 0, rotate cclockwise
-          // if 270, rotate clockwise 
-          (S, Deg0) | (Z, Deg0)=> {
-            next_rot_state = Deg270;
-            dir = CounterClockwise;
-          },
-          (S, Deg270) | (Z, Deg270)=> {
-            next_rot_state = Deg0;
-            dir = Clockwise;
-          },
-          _ => ()
+          (S, Deg0) | (Z, Deg0) => Deg270,
+          (S, Deg270) | (Z, Deg270) => Deg0,
+          _ => self.rot_state.get_next_state(dir),
         };
 
-        // get pivot
-        let (pivot_x, pivot_y) = (current_pos[(0, self.pivot_idx)], current_pos[(1, self.pivot_idx)]);
-
-        use RotationDirection::*;
-        let (r_a, r_b) = match dir { 
-          Clockwise => (-1, 1),
-          CounterClockwise => (1, -1),
-        };
-
-        let t_1 = Matrix3::new( 1, 0, pivot_x,
-                                0, 1, pivot_y,
-                                0, 0, 1 );
-
-        let t_2 = Matrix3::new( 1, 0, -pivot_x,
-                                0, 1, -pivot_y,
-                                0, 0, 1 );
-
-        let rot = Matrix3::new( 0,    r_a, 0,
-                                r_b,  0,   0,
-                                0,    0,   1);
-
-        (t_1 * rot * t_2 * current_pos, next_rot_state)
+        Self {
+          rot_state,
+          offsets: Self::classic_offsets(self.block_type, rot_state),
+          ..*self
+        }
       }
     }
   }
 
-  pub fn try_move(&self, dir: MoveDirection) -> Matrix3x4<isize> {
-    let pos = self.pos.clone();
-
+  pub fn try_move(&self, dir: MoveDirection) -> Self {
     use MoveDirection::*;
     let (d_x, d_y) = match dir {
       Down => (0, 1),
@@ -201,13 +286,11 @@ impl Block {
       Right => (1, 0),
     };
 
-    let t = Matrix3::new(1, 0, d_x,
-                         0, 1, d_y,
-                         0, 0, 1 );
-
-    t * pos
+    Self {
+      origin: (self.origin.0 + d_x, self.origin.1 + d_y),
+      ..*self
+    }
   }
-
 }
 
 pub struct Dimensions {
@@ -219,23 +302,213 @@ pub struct TetrisBuilder {
   pub width: usize,
   pub height: usize,
   pub randomizer: Box<dyn Randomizer<u32>>,
+  pub rotation_system: Box<dyn RotationSystem>,
+  pub piece_generator: Box<dyn PieceGenerator>,
+  pub board: Option<Board>,
+  pub preview: usize, // number of upcoming blocks that are known ahead
+  pub queue: Vec<BlockType>, // blocks to deal before the piece generator is used, starting with the first block
+  pub hold: Option<BlockType>,
+  pub history: Option<usize>, // number of locks that can be undone, None disables undo
+  pub speed_table: SpeedTable,
+  pub dig: Option<Dig>,
+  pub modifiers: Modifiers,
+  pub coop: bool, // two players share the playfield, see `with_coop`
 }
 
 impl TetrisBuilder {
+  /**
+   * Blocks are dealt with a `Pcg32` seeded with 0 unless another seed or randomizer is given,
+   * the speed follows the guideline table
+   */
+  pub fn new(width: usize, height: usize) -> Self {
+    Self {
+      width,
+      height,
+      randomizer: Box::new(Pcg32::new(0)),
+      rotation_system: Box::new(ClassicRotation),
+      piece_generator: Box::new(Uniform),
+      board: None,
+      preview: 5,
+      queue: Vec::new(),
+      hold: None,
+      history: None,
+      speed_table: SpeedTable::guideline(),
+      dig: None,
+      modifiers: Modifiers::default(),
+      coop: false,
+    }
+  }
+
+  /**
+   * Games with the same seed and settings deal the same blocks
+   */
+  pub fn with_seed(mut self, seed: u64) -> Self {
+    self.randomizer = Box::new(Pcg32::new(seed));
+    self
+  }
+
+  pub fn with_randomizer(mut self, randomizer: Box<dyn Randomizer<u32>>) -> Self {
+    self.randomizer = randomizer;
+    self
+  }
+
+  pub fn with_rotation_system(mut self, rotation_system: Box<dyn RotationSystem>) -> Self {
+    self.rotation_system = rotation_system;
+    self
+  }
+
+  pub fn with_piece_generator(mut self, piece_generator: Box<dyn PieceGenerator>) -> Self {
+    self.piece_generator = piece_generator;
+    self
+  }
+
+  /**
+   * Starts with the given playfield instead of an empty one, the dimensions are taken from the board
+   */
+  pub fn with_board(mut self, board: Board) -> Self {
+    self.width = board.width;
+    self.height = board.height;
+    self.board = Some(board);
+    self
+  }
+
+  pub fn with_preview(mut self, preview: usize) -> Self {
+    self.preview = preview;
+    self
+  }
+
+  pub fn with_queue(mut self, queue: Vec<BlockType>) -> Self {
+    self.queue = queue;
+    self
+  }
+
+  pub fn with_hold(mut self, hold: Option<BlockType>) -> Self {
+    self.hold = hold;
+    self
+  }
+
+  /**
+   * Keeps the last locks so they can be undone, see `Tetris::undo`
+   */
+  pub fn with_history(mut self, limit: usize) -> Self {
+    self.history = Some(limit);
+    self
+  }
+
+  /**
+   * Gravity and delays per level, see `SpeedTable`
+   */
+  pub fn with_speed_table(mut self, speed_table: SpeedTable) -> Self {
+    self.speed_table = speed_table;
+    self
+  }
+
+  /**
+   * Adds garbage rows below the board, the game is finished once they are all cleared
+   */
+  pub fn with_dig(mut self, dig: Dig) -> Self {
+    self.dig = Some(dig);
+    self
+  }
+
+  /**
+   * Locked cells disappear from the frame after the given number of seconds
+   */
+  pub fn with_invisible(mut self, seconds: u32) -> Self {
+    self.modifiers.invisible = Some(seconds * FRAMES_PER_SECOND);
+    self
+  }
+
+  /**
+   * Blocks are 2x2 cells, the game is played on a grid of half the size unless a board is given, which is used as that grid
+   */
+  pub fn with_big(mut self) -> Self {
+    self.modifiers.big = true;
+    self
+  }
+
+  /**
+   * Flips the playfield left to right, together with moves and rotations
+   */
+  pub fn with_mirror(mut self) -> Self {
+    self.modifiers.mirror = true;
+    self
+  }
+
+  pub fn with_monochrome(mut self) -> Self {
+    self.modifiers.monochrome = true;
+    self
+  }
+
+  /**
+   * Two players on one wide playfield, each with a block of their own that spawns above their half.
+   * The blocks collide with each other, see `Tetris::do_player_action`. Undo is not available
+   */
+  pub fn with_coop(mut self) -> Self {
+    self.coop = true;
+    self
+  }
+
   pub fn build(self) -> Tetris {
+    let scale = if self.board.is_some() { 1 } else { self.modifiers.scale() };
     let dim = Dimensions {
-      width: self.width,
-      height: self.height,
+      width: self.width / scale,
+      height: self.height / scale,
+    };
+    let lock_frames = self.modifiers.invisible.map(|_| vec![0; dim.width * dim.height]);
+    let playfield = RefCell::new(self.board.map_or_else(|| vec![0; dim.width * dim.height], |board| board.cells));
+    let queue = self.queue.into();
+    let history = if self.coop { None } else { self.history };
+    let (columns, partner) = if self.coop {
+      let half = dim.width / 2;
+      ((0, half), Some(Box::new(Player::new((half as isize, dim.width - half)))))
+    } else {
+      ((0, dim.width), None)
     };
-    let playfield = RefCell::new(vec![0; dim.width * dim.height]);
-    let mut randomizer = self.randomizer;
-    let curr_block = Block::new(BlockType::get_random(&mut randomizer));
-    Tetris {
-      randomizer,
+    let zobrist = Zobrist::new(dim.width, dim.height);
+    let mut tetris = Tetris {
+      randomizer: self.randomizer,
+      rotation_system: self.rotation_system,
+      piece_generator: self.piece_generator,
       dim,
-      curr_block,
+      curr_block: Block::new(BlockType::I), // replaced by the first block in the queue
       playfield,
+      history: history.map(|limit| History::new(limit, &queue)),
+      queue,
+      preview: self.preview,
+      hold: self.hold,
+      hold_used: false,
+      phase: Phase::Falling,
+      phase_timer: 0,
+      pending_lock: None,
+      initial_rotation: None,
+      initial_hold: false,
+      held: None,
+      piece_inputs: 0,
+      combo: None,
+      back_to_back: false,
+      score: 0,
+      lines: 0,
+      last_rotated: false,
+      level: self.speed_table.start_level,
+      speed_table: self.speed_table,
+      gravity: 0,
+      dig: self.dig.map(DigState::new),
+      modifiers: self.modifiers,
+      lock_frames,
+      events: Vec::new(),
+      stats: Stats::default(),
+      columns,
+      partner,
+      zobrist,
+    };
+    tetris.zobrist.rehash(&tetris.playfield.borrow());
+    tetris.add_dig_rows();
+    tetris.spawn();
+    if tetris.partner.is_some() {
+      tetris.as_player(1, Tetris::spawn);
     }
+    tetris
   }
 }
 
@@ -247,95 +520,1597 @@ pub struct Tetris {
   pub curr_block: Block,
   pub dim: Dimensions,
   randomizer: Box<dyn Randomizer<u32>>,
+  rotation_system: Box<dyn RotationSystem>,
+  piece_generator: Box<dyn PieceGenerator>,
+  queue: VecDeque<BlockType>,
+  preview: usize,
+  hold: Option<BlockType>,
+  hold_used: bool, // a block can only be held once until the next block spawns
+  combo: Option<u32>, // number of clearing locks in a row minus one, None when the chain is broken
+  back_to_back: bool, // whether the last clear was a tetris or a t-spin
+  score: u64,
+  lines: u32,
+  last_rotated: bool, // whether the last successful action was a rotation, used for t-spin detection
+  phase: Phase,
+  phase_timer: u32, // frames spent in the current phase
+  pending_lock: Option<(Vec<u32>, State)>, // playfield and state before the last lock, saved in the history once the next block spawns
+  initial_rotation: Option<RotationDirection>, // rotation given during the delays, the next block spawns rotated
+  initial_hold: bool, // hold given during the delays, the next block is swapped with the hold as it spawns
+  held: Option<(MoveDirection, u32)>, // direction that is held and for how many frames, it keeps charging during the delays
+  piece_inputs: u32, // moves and rotations of the current block, see `FinesseFault`
+  level: u32,
+  speed_table: SpeedTable,
+  gravity: u32, // fraction of a cell that the current block has fallen, in 16.16 fixed point
+  dig: Option<DigState>,
+  modifiers: Modifiers,
+  lock_frames: Option<Vec<u64>>, // frame each cell locked on, only kept for the invisible modifier
+  events: Vec<TetrisEvent>,
+  stats: Stats,
+  history: Option<History>,
+  columns: (isize, usize), // first column and width of the area the current block spawns above
+  partner: Option<Box<Player>>, // second player of a cooperative game
+  zobrist: Zobrist,
 }
 
 impl Tetris {
 
+  /**
+   * Advances the game clock by one frame, see `FRAMES_PER_SECOND`.
+   * The block falls with the gravity of the current level and locks once it rested on the stack for the lock delay,
+   * then the line clear delay and the entry delay pass before the next block spawns
+   */
+  pub fn tick(&mut self) {
+    if self.phase.is_over() {
+      return;
+    }
+    self.stats.frames += 1;
+
+    if self.dig.as_mut().is_some_and(DigState::tick) {
+      self.rise_garbage();
+    }
+
+    self.tick_player();
+    if self.partner.is_some() {
+      self.as_player(1, Tetris::tick_player);
+    }
+  }
+
+  fn tick_player(&mut self) {
+    if self.phase.is_over() {
+      return;
+    }
+    self.phase_timer += 1;
+    if let Some((_, frames)) = self.held.as_mut() {
+      *frames = frames.saturating_add(1);
+    }
+
+    let speed = self.speed();
+    match self.phase {
+      Phase::Falling | Phase::Locking => {
+        self.auto_repeat(speed);
+        self.fall(speed);
+      }
+      Phase::LineClearAnimating if self.phase_timer >= speed.line_clear_delay => {
+        let cleared_garbage = self.clear_lines();
+        self.enter(cleared_garbage);
+      }
+      Phase::Entry if self.phase_timer >= speed.are => self.spawn_next(),
+      _ => {}
+    }
+  }
+
+  fn set_phase(&mut self, phase: Phase) {
+    self.phase = phase;
+    self.phase_timer = 0;
+  }
+
+  pub fn phase(&self) -> Phase {
+    self.phase
+  }
+
+  // once the held direction is charged the block moves every arr frames, or as far as it goes with an arr of 0
+  fn auto_repeat(&mut self, speed: Speed) {
+    let (dir, frames) = match self.held {
+      Some(held) if held.1 >= speed.das => held,
+      _ => return,
+    };
+    if speed.arr == 0 {
+      while self.move_obj(dir).is_ok() {}
+    } else if (frames - speed.das).is_multiple_of(speed.arr) {
+      let _ = self.move_obj(dir).ok();
+    }
+  }
+
+  fn fall(&mut self, speed: Speed) {
+    self.gravity += speed.gravity;
+    while self.gravity >= G {
+      self.gravity -= G;
+      if self.move_obj(MoveDirection::Down).is_err() {
+        self.gravity = 0;
+        break;
+      }
+    }
+
+    if self.fits(&self.curr_block.try_move(MoveDirection::Down)) {
+      self.set_phase(Phase::Falling);
+    } else {
+      // the frame the block lands on counts towards the lock delay
+      if self.phase == Phase::Falling {
+        self.set_phase(Phase::Locking);
+        self.phase_timer = 1;
+      }
+      if self.phase_timer >= speed.lock_delay {
+        self.lock();
+      }
+    }
+  }
+
+  /**
+   * Moves the current block down one row, locking it if it can not move.
+   * Does nothing while no block is in play
+   */
   pub fn update(&mut self) {
+    if !self.phase.has_block() {
+      return;
+    }
     if self.move_obj(MoveDirection::Down).is_err() {
-      // add the obj in playfield
-      let block_type = self.curr_block.block_type as u32;
-      self
-        .curr_block
-        .pos
-        .column_iter()
-        .for_each(|col| self.playfield.borrow_mut()[self.dim.width * col[(1,0)] as usize + col[(0,0)] as usize] = block_type);
-
-      // clear lines if any
-      let copy_map: Vec<(usize, usize)> = self.playfield.borrow_mut()
-        .chunks_exact(self.dim.width) // get each row
-        .map(|chunk| chunk.iter().any(|&val| val == 0)) // find which rows will stay
-        .enumerate() // get row numbers
-        .filter_map(|(i, line)| if line { Some(i) } else { None }) // filter out the row numbers that will stay (remove the gaps between the rows)
-        .rev() // reverse because we are starting from the last row
-        .scan(self.dim.height, |acc, i| {
-          // pair the row numbers that will stay with the bottom n rows (collapse the rows to the bottom)
-          *acc -= 1;
-          Some((i, *acc))
-        })
-        .collect();
-
-      // TODO clumps, cascade
-      copy_map.iter().for_each(|&(i, j)| {
-        if i != j { // if line is going to drop
-          let src_idx = i * self.dim.width;
-          let dest_idx = j * self.dim.width;
-          self.playfield.borrow_mut().copy_within(src_idx..(src_idx + self.dim.width), dest_idx); // copy over the row that will be dropping
-          self.playfield.borrow_mut()[src_idx..dest_idx].iter_mut().for_each(|i| *i = 0); // clear the line that was moved
+      self.lock();
+    };
+  }
+
+  fn lock(&mut self) {
+    // remember the state before the lock if it can be undone
+    if self.history.is_some() {
+      self.pending_lock = Some((self.playfield.borrow().clone(), self.state()));
+    }
+
+    // locking above the playfield tops out
+    if self.curr_block.cells().iter().any(|&(_, y)| y < 0) {
+      self.top_out();
+      return;
+    }
+
+    // check for t-spins and finesse before the block becomes part of the playfield
+    let t_spin = self.detect_t_spin();
+    self.check_finesse();
+    self.stats.add_piece(self.curr_block.block_type);
+
+    // add the obj in playfield
+    let block_type = self.curr_block.block_type as u32;
+    self
+      .curr_block
+      .cells()
+      .iter()
+      .for_each(|&(x, y)| self.playfield.borrow_mut()[self.dim.width * y as usize + x as usize] = block_type);
+    if let Some(lock_frames) = self.lock_frames.as_mut() {
+      for &(x, y) in self.curr_block.cells().iter() {
+        lock_frames[self.dim.width * y as usize + x as usize] = self.stats.frames;
+      }
+    }
+    for &(x, y) in self.curr_block.cells().iter() {
+      self.zobrist.toggle(x as usize, y as usize);
+    }
+
+    let lines = self.playfield.borrow().chunks_exact(self.dim.width).filter(|row| !row.contains(&0)).count() as u32;
+    self.score_lock(lines, t_spin);
+    self.level = self.speed_table.level_after_lock(self.level, lines, self.lines);
+
+    // the cleared lines stay on the playfield until the line clear delay runs out,
+    // except in cooperative games where the partner could lock into them in the meantime
+    if lines > 0 && self.speed().line_clear_delay > 0 && self.partner.is_none() {
+      self.set_phase(Phase::LineClearAnimating);
+    } else {
+      let cleared_garbage = self.clear_lines();
+      self.enter(cleared_garbage);
+    }
+  }
+
+  /**
+   * Removes the full rows and collapses the rest, returns whether one of them had garbage
+   */
+  fn clear_lines(&mut self) -> bool {
+    let cleared_garbage = self.playfield.borrow()
+      .chunks_exact(self.dim.width)
+      .any(|row| !row.contains(&0) && row.contains(&GARBAGE));
+    let copy_map: Vec<(usize, usize)> = self.playfield.borrow_mut()
+      .chunks_exact(self.dim.width) // get each row
+      .map(|chunk| chunk.contains(&0)) // find which rows will stay
+      .enumerate() // get row numbers
+      .filter_map(|(i, line)| if line { Some(i) } else { None }) // filter out the row numbers that will stay (remove the gaps between the rows)
+      .rev() // reverse because we are starting from the last row
+      .scan(self.dim.height, |acc, i| {
+        // pair the row numbers that will stay with the bottom n rows (collapse the rows to the bottom)
+        *acc -= 1;
+        Some((i, *acc))
+      })
+      .collect();
+
+    // TODO clumps, cascade
+    copy_map.iter().for_each(|&(i, j)| {
+      if i != j { // if line is going to drop
+        let src_idx = i * self.dim.width;
+        let dest_idx = j * self.dim.width;
+        self.playfield.borrow_mut().copy_within(src_idx..(src_idx + self.dim.width), dest_idx); // copy over the row that will be dropping
+        self.playfield.borrow_mut()[src_idx..dest_idx].iter_mut().for_each(|i| *i = 0); // clear the line that was moved
+        if let Some(lock_frames) = self.lock_frames.as_mut() {
+          lock_frames.copy_within(src_idx..(src_idx + self.dim.width), dest_idx);
         }
-      });
+      }
+    });
+    self.zobrist.collapse(&copy_map);
+    self.lift_partner();
+    cleared_garbage
+  }
+
+  /**
+   * Waits for the entry delay, or spawns the next block right away if there is none.
+   * A dig is finished by the lock that clears the last garbage row
+   */
+  fn enter(&mut self, cleared_garbage: bool) {
+    if self.dig.is_some() && cleared_garbage && self.garbage_left() == 0 {
+      self.set_phase(Phase::Finished);
+      self.end_partner(Phase::Finished);
+      self.events.push(TetrisEvent::Finished(self.stats));
+      self.save_lock();
+    } else if self.speed().are > 0 {
+      self.set_phase(Phase::Entry);
+    } else {
+      self.spawn_next();
+    }
+  }
+
+  fn spawn_next(&mut self) {
+    // try to spawn in a new obj
+    self.spawn();
+
+    // initial hold and rotation, from inputs given during the delays
+    if core::mem::take(&mut self.initial_hold) {
+      self.swap_hold();
+    }
+    if let Some(dir) = self.initial_rotation.take() {
+      // the rotated spawn is not kicked, if it does not fit the block keeps the normal orientation
+      let rotated = self.rotation_system.rotate(&self.curr_block, dir, &|_, _| true);
+      if let Some(block) = rotated.filter(|block| self.fits(block)) {
+        self.curr_block = block;
+        self.piece_inputs = 1;
+      }
+    }
+
+    if !self.fits(&self.curr_block) {
+      self.top_out();
+    } else {
+      self.set_phase(Phase::Falling);
+      self.save_lock();
+    }
+  }
+
+  /**
+   * Saves the last lock in the history now that the next block is in play or the game is over
+   */
+  fn save_lock(&mut self) {
+    if let (Some((playfield, before)), Some(_)) = (self.pending_lock.take(), self.history.as_ref()) {
+      let after = self.state();
+      let history = self.history.as_mut().unwrap();
+      history.push(&playfield, &self.playfield.borrow(), before, after);
+    }
+  }
+
+  /**
+   * Ends the line clear and entry delays right away
+   */
+  fn skip_delays(&mut self) {
+    // the lock is undone or redone, so inputs for the next block are dropped
+    self.initial_rotation = None;
+    self.initial_hold = false;
+    if self.phase == Phase::LineClearAnimating {
+      let cleared_garbage = self.clear_lines();
+      self.enter(cleared_garbage);
+    }
+    if self.phase == Phase::Entry {
+      self.spawn_next();
+    }
+  }
+
+  fn spawn(&mut self) {
+    let block_type = self.next_block_type();
+    self.spawn_block(block_type);
+    self.hold_used = false;
+  }
+
+  fn spawn_block(&mut self, block_type: BlockType) {
+    self.curr_block = self.spawn_position(block_type);
+    self.last_rotated = false;
+    self.gravity = 0;
+    self.piece_inputs = 0;
+  }
+
+  /**
+   * Where a block of the type spawns for the current player
+   */
+  fn spawn_position(&self, block_type: BlockType) -> Block {
+    let (offset, width) = self.columns;
+    let mut block = self.rotation_system.spawn(block_type, width);
+    block.origin.0 += offset;
+    block
+  }
 
-      // try to spawn in a new obj
-      self.curr_block = Block::new(BlockType::get_random(&mut self.randomizer));
-      if !self.not_colliding(&self.curr_block.pos) {
-          // clear the board
-          self.playfield.borrow_mut().iter_mut().for_each(|i| *i = 0);
+  /**
+   * Takes the next block from the queue, keeping the preview filled
+   */
+  fn next_block_type(&mut self) -> BlockType {
+    while self.queue.len() <= self.preview {
+      // blocks that were dealt before an undo are dealt again in the same order
+      let block_type = match self.history.as_ref().and_then(History::replayed) {
+        Some(block_type) => block_type,
+        None => self.piece_generator.next(self.randomizer.as_mut()),
       };
+      if let Some(history) = self.history.as_mut() {
+        history.deal(block_type);
+      }
+      self.queue.push_back(block_type);
+    }
+    self.queue.pop_front().unwrap()
+  }
+
+  fn hold_obj(&mut self) {
+    if self.hold_used {
+      return;
+    }
+    self.swap_hold();
+    if !self.fits(&self.curr_block) {
+      self.top_out();
+    }
+  }
+
+  fn swap_hold(&mut self) {
+    let block_type = match self.hold.replace(self.curr_block.block_type) {
+      Some(block_type) => block_type,
+      None => self.next_block_type(),
     };
+    self.spawn_block(block_type);
+    self.hold_used = true;
+  }
+
+  fn top_out(&mut self) {
+    self.set_phase(Phase::GameOver);
+    self.end_partner(Phase::GameOver);
+    self.events.push(TetrisEvent::GameOver(self.stats));
+    self.save_lock();
+  }
+
+  /**
+   * Pushes a row up from the bottom of the playfield, returns true if the top row had cells that were pushed out
+   */
+  fn push_row(&mut self, row: &[u32]) -> bool {
+    let width = self.dim.width;
+    let mut playfield = self.playfield.borrow_mut();
+    let overflow = playfield[..width].iter().any(|&val| val != 0);
+    playfield.copy_within(width.., 0);
+    let len = playfield.len();
+    playfield[len - width..].copy_from_slice(row);
+    self.zobrist.push_row(row);
+    if let Some(lock_frames) = self.lock_frames.as_mut() {
+      lock_frames.copy_within(width.., 0);
+      lock_frames[len - width..].fill(self.stats.frames);
+    }
+    overflow
+  }
+
+  /**
+   * Starts the dig over, with the same holes as before
+   */
+  fn add_dig_rows(&mut self) {
+    let rows = match self.dig.as_mut() {
+      Some(dig) => {
+        dig.reset();
+        dig.dig.rows
+      }
+      None => return,
+    };
+    for _ in 0..rows {
+      let row = self.dig.as_mut().unwrap().row(self.dim.width);
+      self.push_row(&row);
+    }
+  }
+
+  fn rise_garbage(&mut self) {
+    let row = self.dig.as_mut().unwrap().row(self.dim.width);
+    let overflow = self.push_row(&row);
+    self.rows_pushed(1, overflow);
+  }
+
+  /**
+   * Pushes rows of garbage up from the bottom, all with the hole in the same column, like lines sent by an opponent.
+   * Tops out if the stack is pushed out of the playfield
+   */
+  pub fn add_garbage(&mut self, lines: usize, hole: usize) {
+    if self.phase.is_over() || lines == 0 {
+      return;
+    }
+    let row: Vec<u32> = (0..self.dim.width).map(|x| if x == hole { 0 } else { GARBAGE }).collect();
+    let overflow = (0..lines).fold(false, |overflow, _| self.push_row(&row) || overflow);
+    self.rows_pushed(lines, overflow);
+  }
+
+  fn rows_pushed(&mut self, rows: usize, overflow: bool) {
+    // the rows moved, so locks can no longer be undone
+    if let Some(history) = self.history.as_mut() {
+      history.clear();
+    }
+    self.pending_lock = None;
+
+    // the current blocks are pushed up with the stack, so they keep clear of it and of each other
+    if self.phase.has_block() {
+      self.curr_block.origin.1 -= rows as isize;
+    }
+    self.push_partner(rows);
+    if overflow {
+      self.top_out();
+    }
+  }
+
+  /**
+   * Rows with garbage left on the playfield, see `Dig`
+   */
+  pub fn garbage_left(&self) -> usize {
+    self.playfield.borrow().chunks_exact(self.dim.width).filter(|row| row.contains(&GARBAGE)).count()
+  }
+
+  /**
+   * Starts a new game on an empty playfield, the blocks continue from the current queue
+   */
+  pub fn restart(&mut self) {
+    // clear the board
+    self.playfield.borrow_mut().iter_mut().for_each(|i| *i = 0);
+    self.zobrist.rehash(&self.playfield.borrow());
+    self.add_dig_rows();
+    self.combo = None;
+    self.back_to_back = false;
+    self.hold = None;
+    self.score = 0;
+    self.lines = 0;
+    self.level = self.speed_table.start_level;
+    self.stats = Stats::default();
+    if let Some(lock_frames) = self.lock_frames.as_mut() {
+      lock_frames.fill(0);
+    }
+    self.pending_lock = None;
+    self.initial_rotation = None;
+    self.initial_hold = false;
+    self.held = None;
+    // locks of the previous game can not be undone on the new playfield
+    if let Some(history) = self.history.as_mut() {
+      history.clear();
+    }
+    self.spawn();
+    self.set_phase(Phase::Falling);
+    if self.partner.is_some() {
+      self.as_player(1, |tetris| {
+        tetris.hold = None;
+        tetris.initial_rotation = None;
+        tetris.initial_hold = false;
+        tetris.held = None;
+        tetris.spawn();
+        tetris.set_phase(Phase::Falling);
+      });
+    }
+  }
+
+  pub fn is_game_over(&self) -> bool {
+    self.phase == Phase::GameOver
+  }
+
+  /**
+   * Updates the combo and back-to-back chains and the score after a block locks
+   */
+  fn score_lock(&mut self, lines: u32, t_spin: TSpin) {
+    if lines == 0 {
+      // a lock without a clear breaks the combo, but keeps back-to-back
+      self.combo = None;
+      self.score += clear::t_spin_points(t_spin);
+      return;
+    }
+
+    let combo = self.combo.map_or(0, |c| c + 1);
+    let is_difficult = clear::is_difficult(lines, t_spin);
+    let line_clear = LineClear {
+      lines,
+      t_spin,
+      combo,
+      back_to_back: is_difficult && self.back_to_back,
+      // the cleared lines are still on the playfield, every other row has to be empty
+      perfect_clear: self.playfield.borrow().chunks_exact(self.dim.width).all(|row| !row.contains(&0) || row.iter().all(|&val| val == 0)),
+    };
+
+    self.combo = Some(combo);
+    self.back_to_back = is_difficult;
+    self.lines += lines;
+    self.score += line_clear.points();
+    self.stats.lines += lines;
+    self.stats.attack += line_clear.attack();
+    self.events.push(TetrisEvent::LineClear(line_clear));
+  }
+
+  /**
+   * Three corner rule, a T block is in a t-spin if it was rotated into place and at least three of the diagonals of its center are filled,
+   * it is a mini t-spin unless both corners in front of the stem are filled
+   */
+  fn detect_t_spin(&self) -> TSpin {
+    if !matches!(self.curr_block.block_type, BlockType::T) || !self.last_rotated {
+      return TSpin::None;
+    }
+
+    let cells = self.curr_block.cells();
+
+    // the center is the only cell that touches the other three
+    let &(c_x, c_y) = cells
+      .iter()
+      .find(|&&(x, y)| cells.iter().filter(|&&(o_x, o_y)| (o_x - x).abs() + (o_y - y).abs() == 1).count() == 3)
+      .unwrap();
+
+    // the stem is the neighbour that has no neighbour opposite to it
+    let (s_x, s_y) = cells
+      .iter()
+      .map(|&(x, y)| (x - c_x, y - c_y))
+      .find(|&(x, y)| (x, y) != (0, 0) && !cells.contains(&(c_x - x, c_y - y)))
+      .unwrap();
+
+    // walls and floor count as filled, the area above the playfield does not
+    let is_filled = |x: isize, y: isize| !self.is_free(x, y);
+
+    let corners = [(-1, -1), (1, -1), (-1, 1), (1, 1)]
+      .iter()
+      .filter(|&&(x, y)| is_filled(c_x + x, c_y + y))
+      .count();
+
+    // front corners are on both sides of the stem
+    let front_corners = [1, -1]
+      .iter()
+      .filter(|&&k| is_filled(c_x + s_x + k * s_y, c_y + s_y + k * s_x))
+      .count();
+
+    match (corners, front_corners) {
+      (0..=2, _) => TSpin::None,
+      (_, 2) => TSpin::Full,
+      _ => TSpin::Mini,
+    }
+  }
+
+  fn state(&self) -> State {
+    State {
+      block_type: self.curr_block.block_type,
+      hold: self.hold,
+      hold_used: self.hold_used,
+      combo: self.combo,
+      back_to_back: self.back_to_back,
+      score: self.score,
+      lines: self.lines,
+      level: self.level,
+      stats: self.stats,
+      phase: self.phase,
+      queue_len: self.queue.len(),
+      dealt: self.history.as_ref().map_or(0, History::cursor),
+    }
+  }
+
+  fn restore(&mut self, state: State) {
+    self.zobrist.rehash(&self.playfield.borrow());
+    // the playfield changed at once, so all of it is shown again
+    if let Some(lock_frames) = self.lock_frames.as_mut() {
+      lock_frames.fill(self.stats.frames);
+    }
+    self.queue = self.history.as_ref().unwrap().queue(&state);
+    self.hold = state.hold;
+    self.hold_used = state.hold_used;
+    self.combo = state.combo;
+    self.back_to_back = state.back_to_back;
+    self.score = state.score;
+    self.lines = state.lines;
+    self.level = state.level;
+    // the time spent playing is not undone
+    self.stats = Stats { frames: self.stats.frames, ..state.stats };
+    self.spawn_block(state.block_type);
+    self.set_phase(if state.phase.is_over() { state.phase } else { Phase::Falling });
+  }
+
+  /**
+   * Steps back to before the last lock, with the block that was locked back at the top.
+   * Returns false if history is disabled or there is nothing left to undo.
+   * A lock that is still in its line clear or entry delay is finished first
+   */
+  pub fn undo(&mut self) -> bool {
+    if self.history.is_some() {
+      self.skip_delays();
+    }
+    let state = match self.history.as_mut() {
+      Some(history) => history.undo(&mut self.playfield.borrow_mut()),
+      None => None,
+    };
+    state.map(|state| self.restore(state)).is_some()
+  }
+
+  /**
+   * Locks the last undone block again, as long as nothing was locked since the undo
+   */
+  pub fn redo(&mut self) -> bool {
+    if self.history.is_some() {
+      self.skip_delays();
+    }
+    let state = match self.history.as_mut() {
+      Some(history) => history.redo(&mut self.playfield.borrow_mut()),
+      None => None,
+    };
+    state.map(|state| self.restore(state)).is_some()
+  }
+
+  pub fn combo(&self) -> Option<u32> {
+    self.combo
+  }
+
+  pub fn back_to_back(&self) -> bool {
+    self.back_to_back
+  }
+
+  pub fn score(&self) -> u64 {
+    self.score
+  }
+
+  pub fn lines(&self) -> u32 {
+    self.lines
+  }
+
+  pub fn level(&self) -> u32 {
+    self.level
+  }
+
+  /**
+   * Gravity and delays of the current level
+   */
+  pub fn speed(&self) -> Speed {
+    self.speed_table.speed(self.level)
+  }
+
+  /**
+   * Upcoming blocks, the next block first
+   */
+  pub fn queue(&self) -> &VecDeque<BlockType> {
+    &self.queue
+  }
+
+  pub fn hold(&self) -> Option<BlockType> {
+    self.hold
+  }
+
+  /**
+   * Stats of the current game
+   */
+  pub fn stats(&self) -> &Stats {
+    &self.stats
+  }
+
+  pub fn drain_events(&mut self) -> Vec<TetrisEvent> {
+    core::mem::take(&mut self.events)
+  }
+
+  /**
+   * Copy of the locked cells, without the current block
+   */
+  pub fn board(&self) -> Board {
+    Board {
+      width: self.dim.width,
+      height: self.dim.height,
+      cells: self.playfield.borrow().clone(),
+    }
+  }
+
+  /**
+   * Zobrist hash of the playfield occupancy, the current block with its rotation, the hold and the next block,
+   * for transposition tables and duplicate detection. The playfield part is kept up to date as blocks lock and lines clear,
+   * changes made through `playfield` directly are not seen
+   */
+  pub fn zobrist(&self) -> u64 {
+    let piece = if self.phase.has_block() { self.zobrist.piece(&self.curr_block) } else { 0 };
+    piece ^ self.zobrist_board()
+  }
+
+  /**
+   * Zobrist hash as if the block was the current block, like for placements that are searched before they are made
+   */
+  pub fn zobrist_with(&self, block: &Block) -> u64 {
+    self.zobrist.piece(block) ^ self.zobrist_board()
+  }
+
+  fn zobrist_board(&self) -> u64 {
+    self.zobrist.board() ^ self.zobrist.hold(self.hold) ^ self.zobrist.next(self.queue.front().copied()) ^ self.zobrist_partner()
   }
 
   pub fn do_action(&mut self, action: TetrisAction) {
     use TetrisAction::*;
 
+    if self.phase.is_over() {
+      return;
+    }
+    let action = self.modifiers.action(action);
     match action {
-      Move(dir) => {
+      Press(dir) => self.held = Some((dir, 0)),
+      Release => {
+        self.held = None;
+        return;
+      }
+      _ => {}
+    }
+    // rotations and holds during the delays are applied to the next block as it spawns, the last rotation counts
+    if !self.phase.has_block() {
+      match action {
+        Rotate(dir) => self.initial_rotation = Some(dir),
+        Hold => self.initial_hold = true,
+        _ => return,
+      }
+      self.stats.inputs += 1;
+      return;
+    }
+    self.stats.inputs += 1;
+    if finesse::is_input(action) {
+      self.piece_inputs += 1;
+    }
+    match action {
+      Move(dir) | Press(dir) => {
         let _ = self.move_obj(dir).ok();
       }
       Rotate(dir) => self.rotate_obj(dir),
+      Hold => self.hold_obj(),
+      HardDrop => {
+        while self.move_obj(MoveDirection::Down).is_ok() {}
+        self.update();
+      }
+      Release => {}
     };
   }
 
+  /**
+   * Where the block would be after a move or rotation on the current playfield, without changing the game.
+   * Returns None if the block can not move there, and for actions that do not move the block.
+   * The action is flipped by the mirror modifier the same way as in `do_action`
+   */
+  pub fn try_action(&self, block: &Block, action: TetrisAction) -> Option<Block> {
+    match self.modifiers.action(action) {
+      TetrisAction::Move(dir) | TetrisAction::Press(dir) => Some(block.try_move(dir)).filter(|block| self.fits(block)),
+      TetrisAction::Rotate(dir) => self.rotation_system.rotate(block, dir, &|x, y| self.is_free(x, y)),
+      _ => None,
+    }
+  }
+
   fn rotate_obj(&mut self, dir: RotationDirection) {
-    let (next_pos, next_rot_state) = self.curr_block.try_rotate(dir);
-    if self.within_bounds(&next_pos) && self.not_colliding(&next_pos) {
-      self.curr_block.pos = next_pos;
-      self.curr_block.rot_state = next_rot_state;
+    if let Some(block) = self.rotation_system.rotate(&self.curr_block, dir, &|x, y| self.is_free(x, y)) {
+      self.curr_block = block;
+      self.last_rotated = true;
     };
   }
 
   fn move_obj(&mut self, dir: MoveDirection) -> Result<(), ()> { // return a result because we are using this method to check if block will freeze
-    let next_pos = self.curr_block.try_move(dir);
-    if self.within_bounds(&next_pos) && self.not_colliding(&next_pos) {
-      self.curr_block.pos = next_pos;
+    let next_block = self.curr_block.try_move(dir);
+    if self.fits(&next_block) {
+      self.curr_block = next_block;
+      self.last_rotated = false;
       Ok(())
     } else {
       Err(())
     }
   }
 
-  fn not_colliding(&self, pos: &Matrix3x4<isize>) -> bool {
-    pos
-      .column_iter()
-      .all(|col| self.playfield.borrow()[self.dim.width * col[(1, 0)] as usize + col[(0, 0)] as usize] == 0)
+  pub fn fits(&self, block: &Block) -> bool {
+    block
+      .cells()
+      .iter()
+      .all(|&(x, y)| self.is_free(x, y))
+  }
+
+  /**
+   * Whether a block can occupy the cell, the area above the playfield is free
+   */
+  fn is_free(&self, x: isize, y: isize) -> bool {
+    self.is_open(x, y) && !self.partner_covers(x, y)
+  }
+
+  /**
+   * Whether the cell is on the playfield or above it and not part of the stack, active blocks are not checked
+   */
+  fn is_open(&self, x: isize, y: isize) -> bool {
+    x >= 0 &&
+    x < self.dim.width as isize &&
+    y < self.dim.height as isize &&
+    (y < 0 || self.playfield.borrow()[self.dim.width * y as usize + x as usize] == 0)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  struct Zero;
+
+  impl Randomizer<u32> for Zero {
+    fn get_random(&mut self) -> u32 {
+      0
+    }
+  }
+
+  // counts up, so every draw is different
+  struct Counter(u32);
+
+  impl Randomizer<u32> for Counter {
+    fn get_random(&mut self) -> u32 {
+      self.0 += 1;
+      self.0
+    }
+  }
+
+  // deals the given blocks over and over
+  struct Sequence(VecDeque<BlockType>);
+
+  impl PieceGenerator for Sequence {
+    fn next(&mut self, _: &mut dyn Randomizer<u32>) -> BlockType {
+      let block_type = self.0.pop_front().unwrap();
+      self.0.push_back(block_type);
+      block_type
+    }
+  }
+
+  fn tetris(diagram: &str, blocks: &[BlockType]) -> TetrisBuilder {
+    TetrisBuilder::new(0, 0)
+      .with_randomizer(Box::new(Zero))
+      .with_piece_generator(Box::new(Sequence(blocks.iter().copied().collect())))
+      .with_board(diagram.parse().unwrap())
+  }
+
+  // no gravity, so blocks only move with the actions of a test
+  fn speed(line_clear_delay: u32, are: u32) -> Speed {
+    Speed {
+      gravity: 0,
+      lock_delay: 30,
+      line_clear_delay,
+      are,
+      das: 10,
+      arr: 2,
+    }
+  }
+
+  fn drop_block(tetris: &mut Tetris) {
+    while tetris.move_obj(MoveDirection::Down).is_ok() {}
+    tetris.update();
+  }
+
+  fn line_clears(tetris: &mut Tetris) -> Vec<LineClear> {
+    tetris
+      .drain_events()
+      .into_iter()
+      .filter_map(|event| match event {
+        TetrisEvent::LineClear(line_clear) => Some(line_clear),
+        _ => None,
+      })
+      .collect()
+  }
+
+  fn cells(tetris: &Tetris) -> Vec<(isize, isize)> {
+    let mut cells = tetris.curr_block.cells().to_vec();
+    cells.sort_unstable();
+    cells
+  }
+
+  fn assert_board(tetris: &Tetris, diagram: &str) {
+    assert_eq!(tetris.board(), diagram.parse().unwrap());
+  }
+
+  #[test]
+  fn clears_full_lines_and_collapses() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      Zxxxxxxxxx
+      LLLxxxxSSS
+    ", &[BlockType::I]).build();
+
+    drop_block(&mut tetris);
+
+    assert_board(&tetris, "
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      Zxxxxxxxxx
+    ");
+    assert_eq!(tetris.lines(), 1);
+    assert_eq!(tetris.score(), 100);
+  }
+
+  #[test]
+  fn line_clear_and_entry_delays() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      LLLxxxxSSS
+    ", &[BlockType::I])
+      .with_speed_table(SpeedTable::fixed(speed(3, 2)))
+      .with_history(10)
+      .build();
+
+    tetris.do_action(TetrisAction::HardDrop);
+    assert_eq!(tetris.phase(), Phase::LineClearAnimating);
+    assert_eq!(tetris.frame().clearing, vec![3]);
+    assert_eq!(tetris.lines(), 1);
+
+    // the block can not be moved until the next one spawns
+    tetris.do_action(TetrisAction::Move(MoveDirection::Left));
+    assert_eq!(tetris.stats().inputs, 1);
+
+    for _ in 0..3 {
+      tetris.tick();
+    }
+    assert_eq!(tetris.phase(), Phase::Entry);
+    assert_board(&tetris, "
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+    ");
+    tetris.tick();
+    tetris.tick();
+    assert_eq!(tetris.phase(), Phase::Falling);
+
+    // undo during a delay finishes the lock before stepping back
+    tetris.do_action(TetrisAction::HardDrop);
+    assert_eq!(tetris.phase(), Phase::Entry);
+    assert!(tetris.undo());
+    assert_eq!(tetris.phase(), Phase::Falling);
+    assert_board(&tetris, "
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+    ");
+  }
+
+  #[test]
+  fn initial_rotation_and_hold() {
+    use RotationDirection::*;
+    use TetrisAction::*;
+
+    let mut tetris = TetrisBuilder::new(10, 20)
+      .with_rotation_system(Box::new(Srs))
+      .with_queue(vec![BlockType::T, BlockType::I, BlockType::O, BlockType::S])
+      .with_speed_table(SpeedTable::fixed(speed(0, 2)))
+      .build();
+
+    // the last rotation given during the entry delay counts
+    tetris.do_action(HardDrop);
+    tetris.do_action(Rotate(CounterClockwise));
+    tetris.do_action(Rotate(Clockwise));
+    tetris.tick();
+    tetris.tick();
+    assert_eq!(tetris.curr_block.block_type, BlockType::I);
+    assert_eq!(tetris.curr_block.rot_state, RotationState::Deg90);
+
+    tetris.do_action(HardDrop);
+    tetris.do_action(Hold);
+    tetris.tick();
+    tetris.tick();
+    assert_eq!(tetris.hold(), Some(BlockType::O));
+    assert_eq!(tetris.curr_block.block_type, BlockType::S);
+  }
+
+  #[test]
+  fn blocked_initial_rotation_keeps_the_spawn_orientation() {
+    use RotationDirection::*;
+    use TetrisAction::*;
+
+    let mut classic = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+    ", &[BlockType::O, BlockType::I])
+      .with_speed_table(SpeedTable::fixed(speed(0, 2)))
+      .build();
+    for _ in 0..3 {
+      classic.do_action(Move(MoveDirection::Right));
+    }
+    classic.do_action(HardDrop);
+    classic.do_action(Rotate(Clockwise));
+    classic.tick();
+    classic.tick();
+    assert_eq!(classic.phase(), Phase::Falling);
+    assert_eq!(classic.curr_block.block_type, BlockType::I);
+    assert_eq!(classic.curr_block.rot_state, RotationState::Deg0);
+    assert!(classic.try_action(&classic.curr_block, Rotate(Clockwise)).is_none());
+
+    // without trying the kicks that a rotation in the playfield would get
+    let mut srs = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxZxxxxx
+      xxxxZxxxxx
+    ", &[BlockType::O, BlockType::T])
+      .with_rotation_system(Box::new(Srs))
+      .with_speed_table(SpeedTable::fixed(speed(0, 2)))
+      .build();
+    for _ in 0..3 {
+      srs.do_action(Move(MoveDirection::Right));
+    }
+    srs.do_action(HardDrop);
+    srs.do_action(Rotate(Clockwise));
+    srs.tick();
+    srs.tick();
+    assert_eq!(srs.curr_block.block_type, BlockType::T);
+    assert_eq!(srs.curr_block.rot_state, RotationState::Deg0);
+    assert!(srs.try_action(&srs.curr_block, Rotate(Clockwise)).is_some());
+  }
+
+  #[test]
+  fn held_direction_repeats() {
+    use MoveDirection::*;
+    use TetrisAction::*;
+
+    let mut tetris = TetrisBuilder::new(10, 20)
+      .with_queue(vec![BlockType::O, BlockType::O])
+      .with_speed_table(SpeedTable::fixed(speed(0, 4)))
+      .build();
+    let x = tetris.curr_block.origin.0;
+
+    // the block moves once, then again after das frames and every arr frames after that
+    tetris.do_action(Press(Left));
+    assert_eq!(tetris.curr_block.origin.0, x - 1);
+    let moved: Vec<isize> = (0..14).map(|_| {
+      tetris.tick();
+      x - tetris.curr_block.origin.0
+    }).collect();
+    assert_eq!(moved, vec![1, 1, 1, 1, 1, 1, 1, 1, 1, 2, 2, 3, 3, 4]);
+    tetris.do_action(Release);
+    (0..10).for_each(|_| tetris.tick());
+    assert_eq!(tetris.curr_block.origin.0, x - 4);
+    assert_eq!(tetris.stats().inputs, 1);
+
+    // the direction charges during the entry delay, so the next block repeats 4 frames earlier
+    tetris.do_action(HardDrop);
+    tetris.do_action(Press(Right));
+    (0..4).for_each(|_| tetris.tick());
+    assert_eq!(tetris.curr_block.origin.0, x);
+    (0..6).for_each(|_| tetris.tick());
+    assert_eq!(tetris.curr_block.origin.0, x + 1);
+  }
+
+  #[test]
+  fn combo_and_perfect_clear() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      LLLxxxxSSS
+      LLLxxxxSSS
+    ", &[BlockType::I]).build();
+
+    drop_block(&mut tetris);
+    drop_block(&mut tetris);
+
+    let clears = line_clears(&mut tetris);
+    assert_eq!(clears.len(), 2);
+    assert_eq!((clears[0].combo, clears[0].perfect_clear), (0, false));
+    assert_eq!((clears[1].combo, clears[1].perfect_clear), (1, true));
+    assert_eq!(tetris.combo(), Some(1));
+
+    // a lock without a clear breaks the combo
+    drop_block(&mut tetris);
+    assert_eq!(tetris.combo(), None);
+  }
+
+  #[test]
+  fn back_to_back_tetrises() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+    ", &[BlockType::I]).build();
+
+    for _ in 0..2 {
+      tetris.do_action(TetrisAction::Rotate(RotationDirection::Clockwise));
+      for _ in 0..4 {
+        tetris.do_action(TetrisAction::Move(MoveDirection::Right));
+      }
+      drop_block(&mut tetris);
+    }
+
+    let clears = line_clears(&mut tetris);
+    assert!(!clears[0].back_to_back);
+    assert_eq!(clears[1], LineClear {
+      lines: 4,
+      t_spin: TSpin::None,
+      combo: 1,
+      back_to_back: true,
+      perfect_clear: true,
+    });
+    assert!(tetris.back_to_back());
+    assert_eq!(tetris.score(), 800 + 1200 + 3200 + 50);
+  }
+
+  #[test]
+  fn t_spin_double() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      Zxxxxxxxxx
+      xxxZZZZZZZ
+      ZxZZZZZZZZ
+    ", &[BlockType::T]).build();
+
+    use MoveDirection::*;
+    use RotationDirection::*;
+    use TetrisAction::*;
+
+    tetris.do_action(Rotate(CounterClockwise));
+    for _ in 0..3 {
+      tetris.do_action(Move(Left));
+    }
+    for _ in 0..3 {
+      tetris.do_action(Move(Down));
+    }
+    tetris.do_action(Rotate(Clockwise));
+    tetris.update();
+
+    let clears = line_clears(&mut tetris);
+    assert_eq!((clears[0].lines, clears[0].t_spin), (2, TSpin::Full));
+    assert_board(&tetris, "
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      Zxxxxxxxxx
+    ");
+  }
+
+  #[test]
+  fn blocks_stop_at_walls_and_stack() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      Oxxxxxxxxx
+    ", &[BlockType::O]).build();
+
+    for _ in 0..5 {
+      tetris.do_action(TetrisAction::Move(MoveDirection::Left));
+    }
+    assert_eq!(cells(&tetris), vec![(0, 1), (0, 2), (1, 1), (1, 2)]);
+
+    drop_block(&mut tetris);
+    assert_board(&tetris, "
+      xxxxxxxxxx
+      OOxxxxxxxx
+      OOxxxxxxxx
+      Oxxxxxxxxx
+    ");
+  }
+
+  #[test]
+  fn classic_rotation_turns_about_the_pivot() {
+    let block = Block::new(BlockType::T);
+    let rotated = block.try_rotate(RotationDirection::Clockwise);
+    let mut cells = rotated.cells().to_vec();
+    cells.sort_unstable();
+    assert_eq!(cells, vec![(3, 1), (4, 0), (4, 1), (4, 2)]);
+    assert_eq!(rotated.rot_state, RotationState::Deg90);
+    assert_eq!(rotated.try_rotate(RotationDirection::CounterClockwise).cells(), block.cells());
+
+    // S blocks only have two states, either direction toggles between them
+    let block = Block::new(BlockType::S);
+    let rotated = block.try_rotate(RotationDirection::Clockwise);
+    assert_eq!(rotated.rot_state, RotationState::Deg270);
+    assert_eq!(rotated.try_rotate(RotationDirection::CounterClockwise).cells(), block.cells());
+  }
+
+  #[test]
+  fn srs_spawns_flat_side_down() {
+    let tetris = TetrisBuilder::new(10, 20)
+      .with_randomizer(Box::new(Zero))
+      .with_rotation_system(Box::new(Srs))
+      .with_piece_generator(Box::new(Sequence(vec![BlockType::T].into())))
+      .build();
+    assert_eq!(cells(&tetris), vec![(3, 1), (4, 0), (4, 1), (5, 1)]);
+  }
+
+  #[test]
+  fn srs_kicks_off_the_wall() {
+    let mut tetris = TetrisBuilder::new(10, 20)
+      .with_randomizer(Box::new(Zero))
+      .with_rotation_system(Box::new(Srs))
+      .with_piece_generator(Box::new(Sequence(vec![BlockType::I].into())))
+      .build();
+
+    use MoveDirection::*;
+    use RotationDirection::*;
+    use TetrisAction::*;
+
+    tetris.do_action(Rotate(Clockwise));
+    for _ in 0..5 {
+      tetris.do_action(Move(Left));
+    }
+    assert_eq!(cells(&tetris), vec![(0, 0), (0, 1), (0, 2), (0, 3)]);
+
+    // the flat I would stick out of the left wall, so it is kicked two columns to the right
+    tetris.do_action(Rotate(Clockwise));
+    assert_eq!(cells(&tetris), vec![(0, 2), (1, 2), (2, 2), (3, 2)]);
+    assert_eq!(tetris.curr_block.rot_state, RotationState::Deg180);
+  }
+
+  #[test]
+  fn ars_spawns_flat_side_up() {
+    let tetris = TetrisBuilder::new(10, 20)
+      .with_randomizer(Box::new(Zero))
+      .with_rotation_system(Box::new(Ars))
+      .with_piece_generator(Box::new(Sequence(vec![BlockType::T].into())))
+      .build();
+    assert_eq!(cells(&tetris), vec![(3, 0), (4, 0), (4, 1), (5, 0)]);
+  }
+
+  #[test]
+  fn undo_and_redo_locks() {
+    let mut tetris = TetrisBuilder::new(10, 20)
+      .with_randomizer(Box::new(Counter(0)))
+      .with_piece_generator(Box::new(SevenBag::new()))
+      .with_history(10)
+      .build();
+
+    let snapshot = |tetris: &Tetris| (tetris.board(), tetris.queue().clone(), tetris.hold(), tetris.score(), tetris.curr_block.block_type, tetris.stats().pieces);
+    let mut snapshots = vec![snapshot(&tetris)];
+    tetris.do_action(TetrisAction::Hold);
+    for _ in 0..3 {
+      drop_block(&mut tetris);
+      snapshots.push(snapshot(&tetris));
+    }
+
+    assert!(tetris.undo());
+    assert!(tetris.undo());
+    assert_eq!(snapshot(&tetris), snapshots[1]);
+    assert!(tetris.redo());
+    assert_eq!(snapshot(&tetris), snapshots[2]);
+
+    // locking after an undo deals the same blocks again, and the undone lock can no longer be redone
+    assert!(tetris.undo());
+    tetris.do_action(TetrisAction::Move(MoveDirection::Left));
+    drop_block(&mut tetris);
+    assert_eq!(tetris.queue(), &snapshots[2].1);
+    assert!(!tetris.redo());
+
+    while tetris.undo() {}
+    assert_eq!(tetris.board(), snapshots[0].0);
+    assert_eq!(tetris.hold(), Some(snapshots[0].4));
+    assert_eq!(tetris.stats().pieces, 0);
+  }
+
+  #[test]
+  fn stats_and_game_over() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+      ZZZZZZZZZx
+    ", &[BlockType::I, BlockType::O]).build();
+
+    tetris.do_action(TetrisAction::Rotate(RotationDirection::Clockwise));
+    for _ in 0..4 {
+      tetris.do_action(TetrisAction::Move(MoveDirection::Right));
+    }
+    drop_block(&mut tetris);
+    for _ in 0..FRAMES_PER_SECOND * 2 {
+      tetris.tick();
+    }
+
+    let stats = *tetris.stats();
+    assert_eq!((stats.pieces, stats.inputs, stats.lines, stats.attack), (1, 5, 4, 14));
+    assert_eq!(stats.count(BlockType::I), 1);
+    assert_eq!(stats.pps(), 0.5);
+    assert_eq!(stats.kpp(), 5.0);
+    assert_eq!(stats.apm(), 420.0);
+
+    // stack blocks until the playfield tops out
+    while !tetris.is_game_over() {
+      drop_block(&mut tetris);
+    }
+    let game_over = tetris.drain_events().into_iter().find_map(|event| match event {
+      TetrisEvent::GameOver(stats) => Some(stats),
+      _ => None,
+    });
+    let game_over = game_over.unwrap();
+    assert_eq!(game_over.block_counts.iter().sum::<u32>(), game_over.pieces);
+    assert_eq!(game_over.frames, stats.frames);
+
+    tetris.restart();
+    assert_eq!(tetris.score(), 0);
+    assert_eq!(tetris.stats(), &Stats::default());
   }
 
-  fn within_bounds(&self, pos: &Matrix3x4<isize>) -> bool {
-    pos
-      .column_iter()
-      .all(|col| {
-        col[(1, 0)] < self.dim.height as isize &&
-        col[(0, 0)] >= 0 &&
-        col[(0, 0)] < self.dim.width as isize
+  #[test]
+  fn gravity_and_lock_delay_follow_the_speed_table() {
+    let speed = Speed {
+      gravity: G / 2,
+      lock_delay: 3,
+      line_clear_delay: 0,
+      are: 0,
+      das: 10,
+      arr: 2,
+    };
+    let mut tetris = TetrisBuilder::new(10, 4)
+      .with_piece_generator(Box::new(Sequence(vec![BlockType::O].into())))
+      .with_speed_table(SpeedTable::fixed(speed))
+      .build();
+
+    let top = tetris.curr_block.origin.1;
+    tetris.tick();
+    assert_eq!(tetris.curr_block.origin.1, top);
+    tetris.tick();
+    assert_eq!(tetris.curr_block.origin.1, top + 1);
+
+    // the frame the block lands on counts towards the lock delay
+    while tetris.fits(&tetris.curr_block.try_move(MoveDirection::Down)) {
+      tetris.tick();
+    }
+    tetris.tick();
+    assert_eq!(tetris.stats().pieces, 0);
+    tetris.tick();
+    assert_eq!(tetris.stats().pieces, 1);
+
+    // 20G drops the next block onto the stack on its first frame
+    let mut tetris = TetrisBuilder::new(10, 20)
+      .with_speed_table(SpeedTable::tgm_master().starting_at(500))
+      .build();
+    tetris.tick();
+    assert!(!tetris.fits(&tetris.curr_block.try_move(MoveDirection::Down)));
+    assert_eq!(tetris.level(), 500);
+  }
+
+  #[test]
+  fn dig_and_rising_garbage() {
+    let dig = TetrisBuilder::new(10, 20).with_dig(Dig::new(8).with_seed(3)).build();
+    let board = dig.board();
+    let holes: Vec<usize> = board
+      .cells
+      .chunks_exact(10)
+      .skip(12)
+      .map(|row| {
+        assert_eq!(row.iter().filter(|&&val| val == 0).count(), 1);
+        row.iter().position(|&val| val == 0).unwrap()
+      })
+      .collect();
+    assert!(holes.windows(2).all(|pair| pair[0] != pair[1]));
+    assert_eq!(dig.garbage_left(), 8);
+
+    // the finish time and the pieces are reported once the last garbage row is cleared
+    let mut rising = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      GGGxxxxGGG
+    ", &[BlockType::I])
+      .with_dig(Dig::new(0).with_rise(Rise { interval: 3, min_interval: 1, step: 1 }))
+      .build();
+    rising.tick();
+    rising.tick();
+    rising.do_action(TetrisAction::HardDrop);
+    assert_eq!(rising.phase(), Phase::Finished);
+    let finished = rising.drain_events().into_iter().find_map(|event| match event {
+      TetrisEvent::Finished(stats) => Some(stats),
+      _ => None,
+    });
+    assert_eq!(finished.map(|stats| (stats.frames, stats.pieces)), Some((2, 1)));
+
+    // rows rise after 3 frames, then after 2 and then every frame
+    rising.restart();
+    let rises: Vec<usize> = (0..6)
+      .map(|_| {
+        rising.tick();
+        rising.garbage_left()
       })
+      .collect();
+    assert_eq!(rises, vec![0, 0, 1, 1, 2, 3]);
+
+    // a rising game without garbage yet goes on, also when a lock clears lines without garbage
+    let mut quiet = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      LLLxxxxSSS
+    ", &[BlockType::I])
+      .with_dig(Dig::new(0).with_rise(Rise { interval: 60, min_interval: 60, step: 0 }))
+      .build();
+    quiet.do_action(TetrisAction::HardDrop);
+    assert_eq!(quiet.lines(), 1);
+    quiet.do_action(TetrisAction::HardDrop);
+    assert!(!quiet.phase().is_over());
+  }
+
+  #[test]
+  fn modifiers_change_the_frame() {
+    let mut tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      ZZZxxxxxxx
+    ", &[BlockType::O])
+      .with_speed_table(SpeedTable::fixed(speed(0, 0)))
+      .with_mirror()
+      .with_monochrome()
+      .with_invisible(1)
+      .build();
+
+    let frame = tetris.frame();
+    assert_eq!(frame.get(9, 5), Cell { kind: CellKind::Locked, value: GARBAGE });
+    assert_eq!(frame.get(0, 5).kind, CellKind::Empty);
+
+    // moves are flipped along with the frame
+    let x = tetris.curr_block.origin.0;
+    tetris.do_action(TetrisAction::Move(MoveDirection::Left));
+    assert_eq!(tetris.curr_block.origin.0, x + 1);
+
+    // locked cells disappear from the frame but stay on the playfield
+    for _ in 0..FRAMES_PER_SECOND / 2 {
+      tetris.tick();
+    }
+    tetris.do_action(TetrisAction::HardDrop);
+    for _ in 0..FRAMES_PER_SECOND / 2 {
+      tetris.tick();
+    }
+    assert_eq!(tetris.frame().get(9, 5).kind, CellKind::Empty);
+    assert_eq!(tetris.frame().cells.iter().filter(|cell| cell.kind == CellKind::Locked).count(), 4);
+    for _ in 0..FRAMES_PER_SECOND / 2 {
+      tetris.tick();
+    }
+    assert!(tetris.frame().cells.iter().all(|cell| cell.kind != CellKind::Locked));
+    assert_eq!(tetris.board().cells.iter().filter(|&&val| val != 0).count(), 7);
+
+    // big blocks are 2x2 cells on a grid of half the size
+    let tetris = TetrisBuilder::new(10, 20).with_big().build();
+    assert_eq!((tetris.dim.width, tetris.dim.height), (5, 10));
+    let frame = tetris.frame();
+    assert_eq!((frame.width, frame.height), tetris.frame_size());
+    assert_eq!((frame.width, frame.height), (10, 20));
+    assert_eq!(frame.cells.iter().filter(|cell| cell.kind == CellKind::Active).count(), 16);
+  }
+
+  #[test]
+  fn seeded_games_deal_the_same_blocks() {
+    let queue = |seed| TetrisBuilder::new(10, 20).with_seed(seed).build().queue().clone();
+    assert_eq!(queue(7), queue(7));
+    assert_ne!(queue(7), queue(8));
+  }
+
+  #[test]
+  fn coop_blocks_collide_with_each_other() {
+    use MoveDirection::*;
+
+    let mut tetris = TetrisBuilder::new(20, 6)
+      .with_randomizer(Box::new(Zero))
+      .with_piece_generator(Box::new(Sequence(vec![BlockType::O].into())))
+      .with_coop()
+      .build();
+    assert_eq!(tetris.players(), 2);
+    let sorted = |block: &Block| {
+      let mut cells = block.cells().to_vec();
+      cells.sort_unstable();
+      cells
+    };
+    assert_eq!(sorted(tetris.player_block(0).unwrap()), vec![(4, 1), (4, 2), (5, 1), (5, 2)]);
+    assert_eq!(sorted(tetris.player_block(1).unwrap()), vec![(14, 1), (14, 2), (15, 1), (15, 2)]);
+
+    // the blocks stop next to each other
+    for _ in 0..10 {
+      tetris.do_action(TetrisAction::Move(Right));
+    }
+    tetris.do_player_action(1, TetrisAction::Move(Left));
+    assert_eq!(sorted(tetris.player_block(0).unwrap()), vec![(12, 1), (12, 2), (13, 1), (13, 2)]);
+    assert_eq!(sorted(tetris.player_block(1).unwrap()), vec![(14, 1), (14, 2), (15, 1), (15, 2)]);
+    let frame = tetris.frame();
+    assert_eq!(frame.get(12, 5).kind, CellKind::Ghost);
+    assert_eq!(frame.get(15, 5).kind, CellKind::Ghost);
+    assert_eq!(frame.partner.unwrap().queue, vec![BlockType::O; 5]);
+
+    // the second player locks and spawns again, the first one keeps its block
+    tetris.do_player_action(1, TetrisAction::HardDrop);
+    assert_eq!(tetris.board().get(14, 5), BlockType::O as u32);
+    assert_eq!(sorted(tetris.player_block(1).unwrap()), vec![(14, 1), (14, 2), (15, 1), (15, 2)]);
+    assert_eq!(tetris.player_block(0).unwrap().origin, tetris.curr_block.origin);
+
+    // both blocks fall with the clock
+    let origins = (tetris.player_block(0).unwrap().origin, tetris.player_block(1).unwrap().origin);
+    for _ in 0..FRAMES_PER_SECOND {
+      tetris.tick();
+    }
+    assert_eq!(tetris.player_block(0).unwrap().origin.1, origins.0.1 + 1);
+    assert_eq!(tetris.player_block(1).unwrap().origin.1, origins.1.1 + 1);
+  }
+
+  #[test]
+  fn coop_blocks_rise_with_garbage() {
+    use MoveDirection::*;
+
+    let mut tetris = tetris("
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+      xxxxxxxxxxxxxxxxxxxx
+    ", &[BlockType::O])
+      .with_speed_table(SpeedTable::fixed(speed(0, 0)))
+      .with_dig(Dig::new(0).with_rise(Rise { interval: 2, min_interval: 2, step: 0 }))
+      .with_coop()
+      .build();
+
+    // the second block rests on the first one
+    for _ in 0..10 {
+      tetris.do_action(TetrisAction::Move(Down));
+    }
+    for &dir in [Left, Down].iter() {
+      for _ in 0..10 {
+        tetris.do_player_action(1, TetrisAction::Move(dir));
+      }
+    }
+    let origins = (tetris.player_block(0).unwrap().origin, tetris.player_block(1).unwrap().origin);
+    assert_eq!(origins.0.0, origins.1.0);
+    assert_eq!(origins.1.1 + 2, origins.0.1);
+
+    // both go up by the row that rose, the lower one does not pass the upper one
+    tetris.tick();
+    tetris.tick();
+    assert_eq!(tetris.garbage_left(), 1);
+    assert_eq!(tetris.player_block(0).unwrap().origin, (origins.0.0, origins.0.1 - 1));
+    assert_eq!(tetris.player_block(1).unwrap().origin, (origins.1.0, origins.1.1 - 1));
+
+    // lines sent by an opponent push both up by as many rows
+    tetris.add_garbage(2, 0);
+    assert_eq!(tetris.player_block(0).unwrap().origin, (origins.0.0, origins.0.1 - 3));
+    assert_eq!(tetris.player_block(1).unwrap().origin, (origins.1.0, origins.1.1 - 3));
+  }
+
+  #[test]
+  fn frame_shows_active_and_ghost_cells() {
+    let tetris = tetris("
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      xxxxxxxxxx
+      ZZZxxxxxxx
+    ", &[BlockType::O]).build();
+
+    let frame = tetris.frame();
+    assert_eq!(frame.status, Status::Playing);
+    assert_eq!(frame.get(0, 5), Cell { kind: CellKind::Locked, value: BlockType::Z as u32 });
+    assert_eq!(frame.get(4, 1), Cell { kind: CellKind::Active, value: BlockType::O as u32 });
+    assert_eq!(frame.get(4, 4).kind, CellKind::Ghost);
+    assert_eq!(frame.get(5, 5).kind, CellKind::Ghost);
+    assert_eq!(frame.get(3, 5).kind, CellKind::Empty);
+    assert_eq!(frame.queue, vec![BlockType::O; 5]);
   }
 }
\ No newline at end of file
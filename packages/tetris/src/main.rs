@@ -1,5 +1,6 @@
-use tetris::{Tetris, TetrisBuilder, Randomizer, TetrisAction, MoveDirection, RotationDirection};
-use rand::{Rng, rngs::ThreadRng};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use tetris::{to_char, Tetris, TetrisBuilder, TetrisAction, MoveDirection, RotationDirection, CellKind};
 
 trait Renderable {
   fn render(&self);
@@ -7,66 +8,44 @@ trait Renderable {
 
 impl Renderable for Tetris {
   fn render(&self) {
-    let mut playfield = self.playfield.borrow().clone();
+    let frame = self.frame();
 
-    for _ in 0..self.dim.width + 2 {
+    for _ in 0..frame.width + 2 {
       print!("-");
     }
+    println!();
 
-    println!("");
-    print!("|");
-
-    for col in self.curr_block.pos.column_iter() {
-      playfield[col[(1, 0)] as usize * self.dim.width + col[(0, 0)] as usize] = self.curr_block.block_type as u32;
-    }
-
-    for (i, p) in playfield.iter().enumerate() {
-
-      let c = match *p {
-        1 => 'I',
-        2 => 'J',
-        3 => 'L',
-        4 => 'O',
-        5 => 'S',
-        6 => 'T',
-        7 => 'Z',
-        _ => 'x',
-      };
-
-      if i != 0 && i % self.dim.width == 0 {
-        println!("|");
-        print!("|");
-      }
-      print!("{}", c);
+    for row in frame.cells.chunks_exact(frame.width) {
+      let line: String = row
+        .iter()
+        .map(|cell| match cell.kind {
+          CellKind::Empty => ' ',
+          CellKind::Ghost => '.',
+          CellKind::Locked | CellKind::Active => to_char(cell.value),
+        })
+        .collect();
+      println!("|{}|", line);
     }
 
-    println!("|");
-    for _ in 0..self.dim.width + 2 {
+    for _ in 0..frame.width + 2 {
       print!("-");
     }
+    println!();
+
+    let queue: String = frame.queue.iter().map(|&block_type| to_char(block_type as u32)).collect();
+    println!("score {}  lines {}  hold {:?}  next {}", frame.score, frame.lines, frame.hold, queue);
   }
 }
 
 fn main() {
   println!("Hello, world!");
 
-  struct MyRandomizer {
-      rng: ThreadRng,
-  }
-
-  impl Randomizer<u32> for MyRandomizer {
-      fn get_random(&mut self) -> u32 {
-          self.rng.gen_range(1..=7)
-      }
-  }
+  // a different game every run
+  let seed = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |time| time.as_nanos() as u64);
 
-  let mut tetris = TetrisBuilder {
-    width: 10,
-    height: 20,
-    randomizer: Box::new(MyRandomizer { 
-      rng: rand::thread_rng()
-    }),
-  }.build();
+  let mut tetris = TetrisBuilder::new(10, 20)
+    .with_seed(seed)
+    .build();
 
   use MoveDirection::*;
   use RotationDirection::*;
@@ -0,0 +1,76 @@
+use alloc::vec::Vec;
+
+use crate::{CellKind, Frame, MoveDirection, RotationDirection, TetrisAction, GARBAGE};
+
+/**
+ * Changes to how the game is played or shown, applied to the frame so every renderer gets them
+ */
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Modifiers {
+  pub invisible: Option<u32>, // locked cells disappear from the frame after this many frames, see `FRAMES_PER_SECOND`
+  pub big: bool, // blocks are 2x2 cells, the game is played on a grid of half the width and height
+  pub mirror: bool, // the frame is flipped left to right and so are moves and rotations
+  pub monochrome: bool, // every block has the value of garbage in the frame
+}
+
+impl Modifiers {
+  /**
+   * Cells of the frame for every cell of the game, in both directions
+   */
+  pub fn scale(&self) -> usize {
+    if self.big { 2 } else { 1 }
+  }
+
+  /**
+   * The input as it applies to the flipped playfield
+   */
+  pub(crate) fn action(&self, action: TetrisAction) -> TetrisAction {
+    use MoveDirection::*;
+    use RotationDirection::*;
+    use TetrisAction::*;
+
+    if !self.mirror {
+      return action;
+    }
+    match action {
+      Move(Left) => Move(Right),
+      Move(Right) => Move(Left),
+      Press(Left) => Press(Right),
+      Press(Right) => Press(Left),
+      Rotate(Clockwise) => Rotate(CounterClockwise),
+      Rotate(CounterClockwise) => Rotate(Clockwise),
+      action => action,
+    }
+  }
+
+  /**
+   * Applies monochrome, mirror and big to a frame of the game grid
+   */
+  pub(crate) fn apply(&self, mut frame: Frame) -> Frame {
+    if self.monochrome {
+      frame.cells.iter_mut().filter(|cell| cell.kind != CellKind::Empty).for_each(|cell| cell.value = GARBAGE);
+    }
+
+    if self.mirror {
+      frame.cells.chunks_exact_mut(frame.width).for_each(|row| row.reverse());
+    }
+
+    if self.big {
+      let width = frame.width;
+      frame.cells = frame
+        .cells
+        .chunks_exact(width)
+        .flat_map(|row| {
+          let row: Vec<_> = row.iter().flat_map(|&cell| [cell, cell]).collect();
+          [row.clone(), row]
+        })
+        .flatten()
+        .collect();
+      frame.clearing = frame.clearing.iter().flat_map(|&y| [2 * y, 2 * y + 1]).collect();
+      frame.width *= 2;
+      frame.height *= 2;
+    }
+
+    frame
+  }
+}
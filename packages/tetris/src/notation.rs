@@ -0,0 +1,237 @@
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{MoveDirection, RotationDirection, TetrisAction, GARBAGE};
+
+/**
+ * A copy of the playfield that can be printed and parsed as a grid of letters,
+ * one row per line with `I`, `J`, `L`, `O`, `S`, `T`, `Z` for blocks, `G` for garbage and `x` for empty cells
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Board {
+  pub width: usize,
+  pub height: usize,
+  pub cells: Vec<u32>,
+}
+
+impl Board {
+  pub fn new(width: usize, height: usize) -> Self {
+    Self {
+      width,
+      height,
+      cells: vec![0; width * height],
+    }
+  }
+
+  pub fn get(&self, x: usize, y: usize) -> u32 {
+    self.cells[y * self.width + x]
+  }
+
+  pub fn set(&mut self, x: usize, y: usize, val: u32) {
+    self.cells[y * self.width + x] = val;
+  }
+}
+
+pub fn to_char(val: u32) -> char {
+  match val {
+    1 => 'I',
+    2 => 'J',
+    3 => 'L',
+    4 => 'O',
+    5 => 'S',
+    6 => 'T',
+    7 => 'Z',
+    GARBAGE => 'G',
+    _ => 'x',
+  }
+}
+
+pub fn from_char(c: char) -> Option<u32> {
+  match c {
+    'x' => Some(0),
+    'I' => Some(1),
+    'J' => Some(2),
+    'L' => Some(3),
+    'O' => Some(4),
+    'S' => Some(5),
+    'T' => Some(6),
+    'Z' => Some(7),
+    'G' => Some(GARBAGE),
+    _ => None,
+  }
+}
+
+impl fmt::Display for Board {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    // a board without columns has no rows to write either
+    if self.width == 0 {
+      return Ok(());
+    }
+    for (i, row) in self.cells.chunks_exact(self.width).enumerate() {
+      if i != 0 {
+        writeln!(f)?;
+      }
+      for &val in row {
+        write!(f, "{}", to_char(val))?;
+      }
+    }
+    Ok(())
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParseBoardError {
+  Empty,
+  UnevenRow(usize), // row number that does not have the width of the first row
+  UnknownCell(char),
+}
+
+impl fmt::Display for ParseBoardError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Self::Empty => write!(f, "board has no rows"),
+      Self::UnevenRow(row) => write!(f, "row {} has a different width than the first row", row),
+      Self::UnknownCell(c) => write!(f, "unknown cell '{}'", c),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseBoardError {}
+
+impl FromStr for Board {
+  type Err = ParseBoardError;
+
+  /**
+   * Surrounding whitespace is ignored, so diagrams can be written as indented multiline strings
+   */
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let rows: Vec<&str> = s
+      .lines()
+      .map(|line| line.trim())
+      .filter(|line| !line.is_empty())
+      .collect();
+
+    let width = rows.first().ok_or(ParseBoardError::Empty)?.chars().count();
+    let mut cells = Vec::with_capacity(width * rows.len());
+
+    for (i, row) in rows.iter().enumerate() {
+      if row.chars().count() != width {
+        return Err(ParseBoardError::UnevenRow(i));
+      }
+      for c in row.chars() {
+        cells.push(from_char(c).ok_or(ParseBoardError::UnknownCell(c))?);
+      }
+    }
+
+    Ok(Self {
+      width,
+      height: rows.len(),
+      cells,
+    })
+  }
+}
+
+/**
+ * Actions are written as `left`, `right`, `down`, `cw`, `ccw`, `hold` and `drop`, for action logs and network play.
+ * Held directions are written as `press-left`, `press-right` and `press-down` until `release`
+ */
+impl fmt::Display for TetrisAction {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    use MoveDirection::*;
+    use RotationDirection::*;
+
+    let name = match self {
+      Self::Move(Left) => "left",
+      Self::Move(Right) => "right",
+      Self::Move(Down) => "down",
+      Self::Rotate(Clockwise) => "cw",
+      Self::Rotate(CounterClockwise) => "ccw",
+      Self::Hold => "hold",
+      Self::HardDrop => "drop",
+      Self::Press(Left) => "press-left",
+      Self::Press(Right) => "press-right",
+      Self::Press(Down) => "press-down",
+      Self::Release => "release",
+    };
+    write!(f, "{}", name)
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ParseActionError;
+
+impl fmt::Display for ParseActionError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "action has to be one of left, right, down, cw, ccw, hold, drop, press-left, press-right, press-down or release")
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseActionError {}
+
+impl FromStr for TetrisAction {
+  type Err = ParseActionError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    use MoveDirection::*;
+    use RotationDirection::*;
+
+    match s {
+      "left" => Ok(Self::Move(Left)),
+      "right" => Ok(Self::Move(Right)),
+      "down" => Ok(Self::Move(Down)),
+      "cw" => Ok(Self::Rotate(Clockwise)),
+      "ccw" => Ok(Self::Rotate(CounterClockwise)),
+      "hold" => Ok(Self::Hold),
+      "drop" => Ok(Self::HardDrop),
+      "press-left" => Ok(Self::Press(Left)),
+      "press-right" => Ok(Self::Press(Right)),
+      "press-down" => Ok(Self::Press(Down)),
+      "release" => Ok(Self::Release),
+      _ => Err(ParseActionError),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn round_trip() {
+    let diagram = "xxxx\nxTxx\nTTTx\nIIII";
+    let board: Board = diagram.parse().unwrap();
+    assert_eq!(board.width, 4);
+    assert_eq!(board.height, 4);
+    assert_eq!(board.get(1, 1), 6);
+    assert_eq!(board.to_string(), diagram);
+    assert_eq!(Board::new(0, 4).to_string(), "");
+  }
+
+  #[test]
+  fn ignores_indentation() {
+    let board: Board = "
+      xxx
+      LLL
+    ".parse().unwrap();
+    assert_eq!(board.cells, vec![0, 0, 0, 3, 3, 3]);
+  }
+
+  #[test]
+  fn rejects_bad_diagrams() {
+    assert_eq!("".parse::<Board>(), Err(ParseBoardError::Empty));
+    assert_eq!("xxx\nxx".parse::<Board>(), Err(ParseBoardError::UnevenRow(1)));
+    assert_eq!("xxx\nx?x".parse::<Board>(), Err(ParseBoardError::UnknownCell('?')));
+  }
+
+  #[test]
+  fn actions_round_trip() {
+    for action in "left right down cw ccw hold drop press-left press-right press-down release".split(' ') {
+      assert_eq!(action.parse::<TetrisAction>().unwrap().to_string(), action);
+    }
+    assert_eq!("up".parse::<TetrisAction>(), Err(ParseActionError));
+  }
+}
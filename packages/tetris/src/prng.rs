@@ -0,0 +1,63 @@
+use crate::Randomizer;
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const DEFAULT_STREAM: u64 = 0xda3e39cb94b95bdb;
+
+/**
+ * PCG-XSH-RR random number generator, gives the same numbers for the same seed on every platform
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pcg32 {
+  state: u64,
+  inc: u64,
+}
+
+impl Pcg32 {
+  pub fn new(seed: u64) -> Self {
+    Self::with_stream(seed, DEFAULT_STREAM)
+  }
+
+  /**
+   * Generators with the same seed but different streams give unrelated numbers
+   */
+  pub fn with_stream(seed: u64, stream: u64) -> Self {
+    let mut pcg = Self {
+      state: 0,
+      inc: (stream << 1) | 1,
+    };
+    pcg.step();
+    pcg.state = pcg.state.wrapping_add(seed);
+    pcg.step();
+    pcg
+  }
+
+  fn step(&mut self) {
+    self.state = self.state.wrapping_mul(MULTIPLIER).wrapping_add(self.inc);
+  }
+
+  pub fn next_u32(&mut self) -> u32 {
+    let old = self.state;
+    self.step();
+    let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+    xorshifted.rotate_right((old >> 59) as u32)
+  }
+}
+
+impl Randomizer<u32> for Pcg32 {
+  fn get_random(&mut self) -> u32 {
+    self.next_u32()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn matches_reference_output() {
+    // first numbers of the pcg32 demo in the reference implementation
+    let mut pcg = Pcg32::with_stream(42, 54);
+    let numbers: Vec<u32> = (0..6).map(|_| pcg.next_u32()).collect();
+    assert_eq!(numbers, vec![0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e]);
+  }
+}
@@ -0,0 +1,177 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+
+use crate::{Tetris, TetrisAction, TetrisBuilder};
+
+/**
+ * Seed and inputs of a game, played again on a game that is built the same way they give the same game.
+ * Written as the seed, one input per line with the frame it was given on, and the frame the game ended on:
+ *
+ * ```text
+ * seed 42
+ * 120 left
+ * 131 drop
+ * end 3600
+ * ```
+ */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Replay {
+  pub seed: u64,
+  pub inputs: Vec<(u64, TetrisAction)>, // frame of the game clock and action, see `Tetris::tick`
+  pub frames: u64, // frames the game ran for
+}
+
+impl Replay {
+  pub fn new(seed: u64) -> Self {
+    Self {
+      seed,
+      ..Self::default()
+    }
+  }
+
+  /**
+   * Adds an action given on the current frame of the game, before it is done
+   */
+  pub fn record(&mut self, tetris: &Tetris, action: TetrisAction) {
+    self.frames = tetris.stats().frames;
+    self.inputs.push((self.frames, action));
+  }
+
+  /**
+   * Takes the frame the game is at as the end of the replay, like when the game is over
+   */
+  pub fn finish(&mut self, tetris: &Tetris) {
+    self.frames = tetris.stats().frames;
+  }
+
+  /**
+   * Plays the inputs on a game from the builder with the seed of the replay.
+   * Returns None if the inputs are not in the order of their frames or come after the end
+   */
+  pub fn play(&self, builder: TetrisBuilder) -> Option<Tetris> {
+    fn tick_to(tetris: &mut Tetris, frame: u64) {
+      while tetris.stats().frames < frame && !tetris.phase().is_over() {
+        tetris.tick();
+      }
+    }
+
+    let mut tetris = builder.with_seed(self.seed).build();
+    let mut last = 0;
+    for &(frame, action) in self.inputs.iter() {
+      if frame < last || frame > self.frames {
+        return None;
+      }
+      last = frame;
+      tick_to(&mut tetris, frame);
+      tetris.do_action(action);
+    }
+    tick_to(&mut tetris, self.frames);
+    Some(tetris)
+  }
+}
+
+impl TetrisBuilder {
+  /**
+   * The game that scores are submitted from, the browser game is built from it and submitted replays are played again on it
+   */
+  pub fn leaderboard() -> Self {
+    Self::new(10, 20)
+  }
+}
+
+impl fmt::Display for Replay {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    writeln!(f, "seed {}", self.seed)?;
+    for (frame, action) in self.inputs.iter() {
+      writeln!(f, "{} {}", frame, action)?;
+    }
+    write!(f, "end {}", self.frames)
+  }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ParseReplayError {
+  Seed, // the first line is not the seed
+  Line(usize), // line number that is not an input or the end
+  End, // the end is missing
+}
+
+impl fmt::Display for ParseReplayError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      Self::Seed => write!(f, "replay has to start with a line like seed 42"),
+      Self::Line(line) => write!(f, "line {} is not an input like 120 left", line),
+      Self::End => write!(f, "replay has to end with a line like end 3600"),
+    }
+  }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseReplayError {}
+
+impl FromStr for Replay {
+  type Err = ParseReplayError;
+
+  /**
+   * Surrounding whitespace and empty lines are ignored
+   */
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let mut lines = s.lines().map(|line| line.trim()).enumerate().filter(|(_, line)| !line.is_empty());
+
+    let seed = lines
+      .next()
+      .and_then(|(_, line)| line.strip_prefix("seed "))
+      .and_then(|seed| seed.trim().parse().ok())
+      .ok_or(ParseReplayError::Seed)?;
+    let mut replay = Self::new(seed);
+
+    let mut end = None;
+    for (i, line) in lines {
+      let err = ParseReplayError::Line(i);
+      let words: Vec<&str> = line.split_whitespace().collect();
+      match words.as_slice() {
+        _ if end.is_some() => return Err(err), // nothing comes after the end
+        ["end", frames] => end = Some(frames.parse().map_err(|_| err)?),
+        [frame, action] => replay.inputs.push((frame.parse().map_err(|_| err)?, action.parse().map_err(|_| err)?)),
+        _ => return Err(err),
+      }
+    }
+    replay.frames = end.ok_or(ParseReplayError::End)?;
+    Ok(replay)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::MoveDirection;
+
+  #[test]
+  fn plays_the_same_game_again() {
+    let mut tetris = TetrisBuilder::new(10, 20).with_seed(42).build();
+    let mut replay = Replay::new(42);
+    let actions = [TetrisAction::Move(MoveDirection::Left), TetrisAction::HardDrop, TetrisAction::Hold];
+    for (i, &action) in actions.iter().cycle().take(60).enumerate() {
+      (0..i % 7).for_each(|_| tetris.tick());
+      replay.record(&tetris, action);
+      tetris.do_action(action);
+    }
+    (0..100).for_each(|_| tetris.tick());
+    replay.finish(&tetris);
+
+    let text = replay.to_string();
+    assert!(text.starts_with("seed 42\n0 left\n1 drop\n"));
+    assert_eq!(text.parse(), Ok(replay.clone()));
+
+    let copy = replay.play(TetrisBuilder::new(10, 20)).unwrap();
+    assert_eq!(copy.board(), tetris.board());
+    assert_eq!(copy.score(), tetris.score());
+    assert_eq!(copy.stats(), tetris.stats());
+
+    replay.inputs.swap(0, 5);
+    assert!(replay.play(TetrisBuilder::new(10, 20)).is_none());
+    assert_eq!("seed 42\n0 up\nend 0".parse::<Replay>(), Err(ParseReplayError::Line(1)));
+    assert_eq!("seed 42\n0 left".parse::<Replay>(), Err(ParseReplayError::End));
+  }
+}
@@ -0,0 +1,270 @@
+use crate::{Block, BlockType, Offsets, RotationDirection, RotationState};
+
+/**
+ * Decides how blocks spawn and how they rotate, including wall kicks
+ */
+pub trait RotationSystem {
+  /**
+   * Returns the block in its spawn orientation, centered on a playfield that is `width` columns wide
+   */
+  fn spawn(&self, block_type: BlockType, width: usize) -> Block;
+
+  /**
+   * Returns the rotated block, or None if neither the rotation nor any of its kicks fit,
+   * `is_free` tells whether the cell at (x, y) can be occupied
+   */
+  fn rotate(&self, block: &Block, dir: RotationDirection, is_free: &dyn Fn(isize, isize) -> bool) -> Option<Block>;
+}
+
+// the table systems keep the origin at the top left corner of the bounding box
+type Shape = Offsets;
+
+fn fits(block: &Block, is_free: &dyn Fn(isize, isize) -> bool) -> bool {
+  block.cells().iter().all(|&(x, y)| is_free(x, y))
+}
+
+// blocks spawn with their bounding box shifted to the middle, 3 for a playfield of width 10
+fn spawn_column(width: usize) -> isize {
+  (width as isize - 4) / 2
+}
+
+// rotates the shape clockwise inside a bounding box of the given size, y points down
+const fn rotate_shape(shape: Shape, size: i8) -> Shape {
+  let mut rotated = shape;
+  let mut i = 0;
+  while i < 4 {
+    rotated[i] = (size - 1 - shape[i].1, shape[i].0);
+    i += 1;
+  }
+  rotated
+}
+
+// the shapes of every rotation state, in the order of Deg0, Deg90, Deg180 and Deg270
+const fn rotations(spawn: Shape, size: i8) -> [Shape; 4] {
+  let deg_90 = rotate_shape(spawn, size);
+  let deg_180 = rotate_shape(deg_90, size);
+  [spawn, deg_90, deg_180, rotate_shape(deg_180, size)]
+}
+
+// blocks that only have the 0 and 90 states toggle between them in either direction
+fn next_two_state(rot_state: RotationState) -> RotationState {
+  match rot_state {
+    RotationState::Deg0 => RotationState::Deg90,
+    _ => RotationState::Deg0,
+  }
+}
+
+/**
+ * The rotation that the engine started with, blocks rotate about a pivot cell without kicks,
+ * I, S and Z blocks only have two states
+ */
+pub struct ClassicRotation;
+
+impl RotationSystem for ClassicRotation {
+  fn spawn(&self, block_type: BlockType, width: usize) -> Block {
+    let mut block = Block::new(block_type);
+    // the initial positions are for a playfield of width 10
+    block.origin.0 += spawn_column(width) - 3;
+    block
+  }
+
+  fn rotate(&self, block: &Block, dir: RotationDirection, is_free: &dyn Fn(isize, isize) -> bool) -> Option<Block> {
+    let block = block.try_rotate(dir);
+    if fits(&block, is_free) {
+      Some(block)
+    } else {
+      None
+    }
+  }
+}
+
+/**
+ * Super Rotation System, the guideline standard
+ */
+pub struct Srs;
+
+const SRS_SHAPES: [[Shape; 4]; 7] = [
+  rotations([(0, 1), (1, 1), (2, 1), (3, 1)], 4), // I
+  rotations([(0, 0), (0, 1), (1, 1), (2, 1)], 3), // J
+  rotations([(2, 0), (0, 1), (1, 1), (2, 1)], 3), // L
+  [[(1, 0), (2, 0), (1, 1), (2, 1)]; 4], // O
+  rotations([(1, 0), (2, 0), (0, 1), (1, 1)], 3), // S
+  rotations([(1, 0), (0, 1), (1, 1), (2, 1)], 3), // T
+  rotations([(0, 0), (1, 0), (1, 1), (2, 1)], 3), // Z
+];
+
+impl Srs {
+  fn shape(block_type: BlockType, rot_state: RotationState) -> Shape {
+    SRS_SHAPES[block_type as usize - 1][rot_state as usize]
+  }
+
+  // offsets to try in order, in the usual notation where y points up
+  fn kicks(block_type: BlockType, from: RotationState, to: RotationState) -> [(isize, isize); 5] {
+    use RotationState::*;
+    match block_type {
+      BlockType::I => match (from, to) {
+        (Deg0, Deg90) | (Deg270, Deg180) => [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+        (Deg90, Deg0) | (Deg180, Deg270) => [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+        (Deg90, Deg180) | (Deg0, Deg270) => [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+        _ => [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+      },
+      _ => match (from, to) {
+        (Deg0, Deg90) | (Deg180, Deg90) => [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+        (Deg90, Deg0) | (Deg90, Deg180) => [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+        (Deg180, Deg270) | (Deg0, Deg270) => [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+        _ => [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+      },
+    }
+  }
+}
+
+impl RotationSystem for Srs {
+  fn spawn(&self, block_type: BlockType, width: usize) -> Block {
+    Block::from_shape(block_type, RotationState::Deg0, (spawn_column(width), 0), Self::shape(block_type, RotationState::Deg0))
+  }
+
+  fn rotate(&self, block: &Block, dir: RotationDirection, is_free: &dyn Fn(isize, isize) -> bool) -> Option<Block> {
+    if let BlockType::O = block.block_type {
+      return None;
+    }
+
+    let (o_x, o_y) = block.origin;
+    let rot_state = block.rot_state.get_next_state(dir);
+    let shape = Self::shape(block.block_type, rot_state);
+
+    Self::kicks(block.block_type, block.rot_state, rot_state)
+      .iter()
+      .map(|&(k_x, k_y)| Block::from_shape(block.block_type, rot_state, (o_x + k_x, o_y - k_y), shape))
+      .find(|block| fits(block, is_free))
+  }
+}
+
+/**
+ * Arika Rotation System used by TGM, blocks sit flat side up at the bottom of their bounding box,
+ * kicks one column right then left, except for I blocks and for J, L and T blocks that are stopped by the center column
+ */
+pub struct Ars;
+
+impl Ars {
+  fn shape(block_type: BlockType, rot_state: RotationState) -> Shape {
+    use BlockType::*;
+    use RotationState::*;
+    match (block_type, rot_state) {
+      (I, Deg0) | (I, Deg180) => [(0, 1), (1, 1), (2, 1), (3, 1)],
+      (I, _) => [(2, 0), (2, 1), (2, 2), (2, 3)],
+      (J, Deg0) => [(0, 1), (1, 1), (2, 1), (2, 2)],
+      (J, Deg90) => [(1, 0), (1, 1), (0, 2), (1, 2)],
+      (J, Deg180) => [(0, 1), (0, 2), (1, 2), (2, 2)],
+      (J, Deg270) => [(1, 0), (2, 0), (1, 1), (1, 2)],
+      (L, Deg0) => [(0, 1), (1, 1), (2, 1), (0, 2)],
+      (L, Deg90) => [(0, 0), (1, 0), (1, 1), (1, 2)],
+      (L, Deg180) => [(2, 1), (0, 2), (1, 2), (2, 2)],
+      (L, Deg270) => [(1, 0), (1, 1), (1, 2), (2, 2)],
+      (O, _) => [(1, 1), (2, 1), (1, 2), (2, 2)],
+      (S, Deg0) | (S, Deg180) => [(1, 1), (2, 1), (0, 2), (1, 2)],
+      (S, _) => [(0, 0), (0, 1), (1, 1), (1, 2)],
+      (T, Deg0) => [(0, 1), (1, 1), (2, 1), (1, 2)],
+      (T, Deg90) => [(1, 0), (0, 1), (1, 1), (1, 2)],
+      (T, Deg180) => [(1, 1), (0, 2), (1, 2), (2, 2)],
+      (T, Deg270) => [(1, 0), (1, 1), (2, 1), (1, 2)],
+      (Z, Deg0) | (Z, Deg180) => [(0, 1), (1, 1), (1, 2), (2, 2)],
+      (Z, _) => [(2, 0), (1, 1), (2, 1), (1, 2)],
+    }
+  }
+}
+
+impl RotationSystem for Ars {
+  fn spawn(&self, block_type: BlockType, width: usize) -> Block {
+    // the top row of the bounding box is empty in the spawn orientation
+    Block::from_shape(block_type, RotationState::Deg0, (spawn_column(width), -1), Self::shape(block_type, RotationState::Deg0))
+  }
+
+  fn rotate(&self, block: &Block, dir: RotationDirection, is_free: &dyn Fn(isize, isize) -> bool) -> Option<Block> {
+    use BlockType::*;
+    let (o_x, o_y) = block.origin;
+    let rot_state = match block.block_type {
+      O => return None,
+      I | S | Z => next_two_state(block.rot_state),
+      _ => block.rot_state.get_next_state(dir),
+    };
+    let shape = Self::shape(block.block_type, rot_state);
+
+    let rotated = Block::from_shape(block.block_type, rot_state, (o_x, o_y), shape);
+    if fits(&rotated, is_free) {
+      return Some(rotated);
+    }
+
+    let can_kick = match block.block_type {
+      I => false,
+      J | L | T => {
+        // the first blocked cell in reading order must not be in the center column
+        let mut cells = shape;
+        cells.sort_by_key(|&(x, y)| (y, x));
+        !matches!(cells.iter().find(|&&(x, y)| !is_free(o_x + x as isize, o_y + y as isize)), Some(&(1, _)))
+      },
+      _ => true,
+    };
+
+    if !can_kick {
+      return None;
+    }
+
+    [1, -1]
+      .iter()
+      .map(|&k_x| Block::from_shape(block.block_type, rot_state, (o_x + k_x, o_y), shape))
+      .find(|block| fits(block, is_free))
+  }
+}
+
+/**
+ * Nintendo Rotation System, blocks rotate about their center without kicks,
+ * vertical I, S and Z blocks lean to the right of center
+ */
+pub struct Nrs;
+
+const NRS_J: [Shape; 4] = rotations([(0, 1), (1, 1), (2, 1), (2, 2)], 3);
+const NRS_L: [Shape; 4] = rotations([(0, 1), (1, 1), (2, 1), (0, 2)], 3);
+const NRS_T: [Shape; 4] = rotations([(0, 1), (1, 1), (2, 1), (1, 2)], 3);
+
+impl Nrs {
+  fn shape(block_type: BlockType, rot_state: RotationState) -> Shape {
+    use BlockType::*;
+    use RotationState::*;
+    match (block_type, rot_state) {
+      (I, Deg0) => [(0, 2), (1, 2), (2, 2), (3, 2)],
+      (I, _) => [(2, 0), (2, 1), (2, 2), (2, 3)],
+      (J, _) => NRS_J[rot_state as usize],
+      (L, _) => NRS_L[rot_state as usize],
+      (O, _) => [(1, 1), (2, 1), (1, 2), (2, 2)],
+      (S, Deg0) => [(1, 1), (2, 1), (0, 2), (1, 2)],
+      (S, _) => [(1, 0), (1, 1), (2, 1), (2, 2)],
+      (T, _) => NRS_T[rot_state as usize],
+      (Z, Deg0) => [(0, 1), (1, 1), (1, 2), (2, 2)],
+      (Z, _) => [(2, 0), (1, 1), (2, 1), (1, 2)],
+    }
+  }
+}
+
+impl RotationSystem for Nrs {
+  fn spawn(&self, block_type: BlockType, width: usize) -> Block {
+    // the spawn orientation starts at the center row of the bounding box, or one lower for I blocks
+    let o_y = if let BlockType::I = block_type { -2 } else { -1 };
+    Block::from_shape(block_type, RotationState::Deg0, (spawn_column(width), o_y), Self::shape(block_type, RotationState::Deg0))
+  }
+
+  fn rotate(&self, block: &Block, dir: RotationDirection, is_free: &dyn Fn(isize, isize) -> bool) -> Option<Block> {
+    use BlockType::*;
+    let rot_state = match block.block_type {
+      O => return None,
+      I | S | Z => next_two_state(block.rot_state),
+      _ => block.rot_state.get_next_state(dir),
+    };
+
+    let rotated = Block::from_shape(block.block_type, rot_state, block.origin, Self::shape(block.block_type, rot_state));
+    if fits(&rotated, is_free) {
+      Some(rotated)
+    } else {
+      None
+    }
+  }
+}
@@ -0,0 +1,223 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+/**
+ * One cell per frame, gravity is in 16.16 fixed point so slow levels can move less than a cell per frame
+ */
+pub const G: u32 = 1 << 16;
+
+/**
+ * Twenty cells per frame, drops blocks to the floor of a standard playfield on the frame they spawn
+ */
+pub const GRAVITY_20G: u32 = 20 * G;
+
+/**
+ * Timings of one level, the delays are in frames, see `FRAMES_PER_SECOND`
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Speed {
+  pub gravity: u32, // cells per frame in 16.16 fixed point, see `G`
+  pub lock_delay: u32, // frames a block can rest on the stack before it locks
+  pub line_clear_delay: u32, // frames the cleared lines take to disappear
+  pub are: u32, // entry delay, frames between a lock and the next spawn
+  pub das: u32, // frames a direction is held before it repeats, see `TetrisAction::Press`
+  pub arr: u32, // frames between repeated moves once das is charged
+}
+
+/**
+ * How the level goes up as blocks lock
+ */
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LevelUp {
+  Lines(u32), // one level every that many lines
+  Tgm, // one level per block and per cleared line, blocks stop at the last level of every hundred until a line is cleared
+}
+
+/**
+ * Speeds keyed by level, every row applies from its level up to the next row
+ */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpeedTable {
+  pub start_level: u32,
+  pub max_level: u32,
+  pub level_up: LevelUp,
+  rows: Vec<(u32, Speed)>,
+}
+
+// frames that a cell takes to fall, rounded so that the block has moved once the frames have passed
+const fn frames_per_cell(frames: u32) -> u32 {
+  G.div_ceil(frames)
+}
+
+// guideline gravity is (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds per cell, precomputed for levels 1 to 19
+const GUIDELINE_GRAVITY: [u32; 19] = [
+  1093, 1378, 1769, 2311, 3076, 4169, 5759, 8107, 11635, 17027, 25416, 38709, 60169, 95484, 154743, 256187, 433425, 749597, GRAVITY_20G,
+];
+
+// frames per cell of the NTSC version and the first level they apply to
+const NES_GRAVITY: [(u32, u32); 15] = [
+  (0, 48), (1, 43), (2, 38), (3, 33), (4, 28), (5, 23), (6, 18), (7, 13), (8, 8), (9, 6),
+  (10, 5), (13, 4), (16, 3), (19, 2), (29, 1),
+];
+
+// internal gravity of the master mode in 1/256 cells per frame, it slows down again at 200
+const TGM_GRAVITY: [(u32, u32); 30] = [
+  (0, 4), (30, 6), (35, 8), (40, 10), (50, 12), (60, 16), (70, 32), (80, 48), (90, 64), (100, 80),
+  (120, 96), (140, 112), (160, 128), (170, 144), (200, 4), (220, 32), (230, 64), (233, 96), (236, 128), (239, 160),
+  (243, 192), (247, 224), (251, 256), (300, 512), (330, 768), (360, 1024), (400, 1280), (420, 1024), (450, 768), (500, 5120),
+];
+
+// lock delay, line clear delay, are and das of the master mode once the block speed is 20G
+const TGM_DELAYS: [(u32, [u32; 4]); 6] = [
+  (0, [30, 40, 25, 14]),
+  (500, [30, 25, 25, 8]),
+  (600, [30, 16, 25, 8]),
+  (700, [30, 12, 16, 8]),
+  (800, [30, 6, 12, 8]),
+  (900, [17, 6, 12, 6]),
+];
+
+impl SpeedTable {
+  /**
+   * Rows can be given in any order, levels below the first row use the first row
+   */
+  pub fn new(start_level: u32, max_level: u32, level_up: LevelUp, mut rows: Vec<(u32, Speed)>) -> Self {
+    assert!(!rows.is_empty(), "a speed table needs at least one row");
+    rows.sort_by_key(|&(level, _)| level);
+    Self {
+      start_level,
+      max_level,
+      level_up,
+      rows,
+    }
+  }
+
+  /**
+   * The same speed at every level
+   */
+  pub fn fixed(speed: Speed) -> Self {
+    Self::new(1, u32::MAX, LevelUp::Lines(10), vec![(1, speed)])
+  }
+
+  /**
+   * Modern games, levels 1 to 20 with a level every 10 lines and 20G from level 19
+   */
+  pub fn guideline() -> Self {
+    let rows = GUIDELINE_GRAVITY
+      .iter()
+      .enumerate()
+      .map(|(i, &gravity)| {
+        (i as u32 + 1, Speed {
+          gravity,
+          lock_delay: 30,
+          line_clear_delay: 0,
+          are: 0,
+          das: 10,
+          arr: 2,
+        })
+      })
+      .collect();
+    Self::new(1, 20, LevelUp::Lines(10), rows)
+  }
+
+  /**
+   * The NES version, blocks lock as soon as gravity pulls them into the stack
+   */
+  pub fn nes() -> Self {
+    let rows = NES_GRAVITY
+      .iter()
+      .map(|&(level, frames)| {
+        (level, Speed {
+          gravity: frames_per_cell(frames),
+          lock_delay: frames,
+          line_clear_delay: 20,
+          are: 10,
+          das: 16,
+          arr: 6,
+        })
+      })
+      .collect();
+    Self::new(0, 29, LevelUp::Lines(10), rows)
+  }
+
+  /**
+   * Master mode of the arcade games, from levels 0 to 999 with 20G from level 500
+   */
+  pub fn tgm_master() -> Self {
+    // rows change whenever either the gravity or the delays do
+    let mut levels: Vec<u32> = TGM_GRAVITY.iter().map(|&(level, _)| level).chain(TGM_DELAYS.iter().map(|&(level, _)| level)).collect();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let rows = levels
+      .into_iter()
+      .map(|level| {
+        let gravity = TGM_GRAVITY.iter().rev().find(|&&(from, _)| from <= level).unwrap().1;
+        let [lock_delay, line_clear_delay, are, das] = TGM_DELAYS.iter().rev().find(|&&(from, _)| from <= level).unwrap().1;
+        (level, Speed {
+          gravity: gravity * G / 256,
+          lock_delay,
+          line_clear_delay,
+          are,
+          das,
+          arr: 1,
+        })
+      })
+      .collect();
+    Self::new(0, 999, LevelUp::Tgm, rows)
+  }
+
+  /**
+   * Starts games at another level, kept within the levels of the table
+   */
+  pub fn starting_at(mut self, level: u32) -> Self {
+    self.start_level = level.min(self.max_level);
+    self
+  }
+
+  pub fn speed(&self, level: u32) -> Speed {
+    self.rows.iter().rev().find(|&&(from, _)| from <= level).unwrap_or(&self.rows[0]).1
+  }
+
+  /**
+   * Level after a block locks and clears `lines`, `total_lines` already includes them
+   */
+  pub fn level_after_lock(&self, level: u32, lines: u32, total_lines: u32) -> u32 {
+    let level = match self.level_up {
+      LevelUp::Lines(per_level) => level.max(self.start_level + total_lines / per_level.max(1)),
+      LevelUp::Tgm => {
+        // the last level before the end and of every hundred is only passed by clearing lines
+        let stopped = level % 100 == 99 || level + 1 == self.max_level;
+        level + lines + if stopped { 0 } else { 1 }
+      }
+    };
+    level.min(self.max_level)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tables_match_the_original_games() {
+    let guideline = SpeedTable::guideline();
+    assert_eq!(guideline.speed(1).gravity * 60 / G, 1); // a cell per second
+    assert_eq!(guideline.speed(20).gravity, GRAVITY_20G);
+    assert_eq!(guideline.level_after_lock(1, 4, 12), 2);
+
+    let nes = SpeedTable::nes();
+    assert_eq!(nes.speed(11), nes.speed(10));
+    assert_eq!(nes.speed(29).gravity, G);
+
+    let tgm = SpeedTable::tgm_master();
+    assert_eq!(tgm.speed(199).gravity, 144 * G / 256);
+    assert_eq!(tgm.speed(200).gravity, 4 * G / 256);
+    assert_eq!(tgm.speed(500).gravity, GRAVITY_20G);
+    assert_eq!(tgm.speed(950).lock_delay, 17);
+    assert_eq!(tgm.level_after_lock(98, 0, 0), 99);
+    assert_eq!(tgm.level_after_lock(99, 0, 0), 99);
+    assert_eq!(tgm.level_after_lock(99, 2, 2), 101);
+    assert_eq!(tgm.level_after_lock(997, 4, 4), 999);
+  }
+}
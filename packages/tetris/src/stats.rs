@@ -0,0 +1,61 @@
+use crate::BlockType;
+
+/**
+ * Number of times `Tetris::tick` is expected to be called per second
+ */
+pub const FRAMES_PER_SECOND: u32 = 60;
+
+/**
+ * Numbers of a single game, used to compare players
+ */
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+  pub pieces: u32, // blocks that locked in the playfield
+  pub inputs: u32, // actions done by the player
+  pub lines: u32,
+  pub attack: u32, // garbage lines sent, see `LineClear::attack`
+  pub frames: u64, // time played in ticks
+  pub finesse_faults: u32, // blocks that were placed with more inputs than needed
+  pub block_counts: [u32; 7], // locked blocks of each type, in the order of `BlockType::ALL`
+}
+
+impl Stats {
+  pub fn count(&self, block_type: BlockType) -> u32 {
+    self.block_counts[block_type as usize - 1]
+  }
+
+  pub fn seconds(&self) -> f64 {
+    self.frames as f64 / FRAMES_PER_SECOND as f64
+  }
+
+  /**
+   * Pieces per second
+   */
+  pub fn pps(&self) -> f64 {
+    per(self.pieces as f64, self.seconds())
+  }
+
+  /**
+   * Key presses per piece
+   */
+  pub fn kpp(&self) -> f64 {
+    per(self.inputs as f64, self.pieces as f64)
+  }
+
+  /**
+   * Attack per minute
+   */
+  pub fn apm(&self) -> f64 {
+    per(self.attack as f64, self.seconds() / 60.0)
+  }
+
+  pub(crate) fn add_piece(&mut self, block_type: BlockType) {
+    self.pieces += 1;
+    self.block_counts[block_type as usize - 1] += 1;
+  }
+}
+
+// rates are zero until there is something to divide by
+fn per(amount: f64, unit: f64) -> f64 {
+  if unit > 0.0 { amount / unit } else { 0.0 }
+}
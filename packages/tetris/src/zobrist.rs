@@ -0,0 +1,214 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{Block, BlockType, Pcg32};
+
+// keys come from their own fixed stream, so equal states hash the same in every game of the same size
+const KEY_STREAM: u64 = 0x7a6f6272697374;
+
+/**
+ * Finalizer of splitmix64, spreads every bit of the input over the output
+ */
+fn mix(mut z: u64) -> u64 {
+  z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+  z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+  z ^ (z >> 31)
+}
+
+/**
+ * Zobrist keys and the running hash of the playfield occupancy.
+ * Each row hashes to the keys of its filled columns and the playfield to the rows mixed with their height,
+ * so a lock only updates the rows it touched. Line clears and rising rows move row hashes to other heights,
+ * then the rows are summed again without hashing their cells
+ */
+pub(crate) struct Zobrist {
+  columns: Vec<u64>,
+  rows: Vec<u64>,
+  pieces: [u64; 28], // block type and rotation state of the current block
+  hold: [u64; 8], // no hold hashes to 0
+  next: [u64; 8], // first block of the queue, an empty queue hashes to 0
+  partner: u64, // keeps the block, hold and next of the second player of a cooperative game apart from the first
+  row_hashes: Vec<u64>,
+  board: u64,
+}
+
+impl Zobrist {
+  pub fn new(width: usize, height: usize) -> Self {
+    let mut rng = Pcg32::with_stream(0, KEY_STREAM);
+    let mut key = || (rng.next_u32() as u64) << 32 | rng.next_u32() as u64;
+    let columns = (0..width).map(|_| key()).collect();
+    let rows = (0..height).map(|_| key()).collect();
+    let mut pieces = [0; 28];
+    pieces.iter_mut().for_each(|piece| *piece = key());
+    let (mut hold, mut next) = ([0; 8], [0; 8]);
+    hold[1..].iter_mut().for_each(|hold| *hold = key());
+    next[1..].iter_mut().for_each(|next| *next = key());
+    let partner = key();
+    Self {
+      columns,
+      rows,
+      pieces,
+      hold,
+      next,
+      partner,
+      row_hashes: vec![0; height],
+      board: 0,
+    }
+  }
+
+  // empty rows add nothing, so an empty playfield hashes to 0
+  fn row_term(&self, y: usize) -> u64 {
+    match self.row_hashes[y] {
+      0 => 0,
+      row => mix(row ^ self.rows[y]),
+    }
+  }
+
+  fn sum_rows(&mut self) {
+    self.board = (0..self.row_hashes.len()).fold(0, |board, y| board ^ self.row_term(y));
+  }
+
+  fn hash_row(&self, row: &[u32]) -> u64 {
+    row.iter().zip(self.columns.iter()).filter(|(&val, _)| val != 0).fold(0, |hash, (_, &key)| hash ^ key)
+  }
+
+  /**
+   * Hashes the whole playfield again, for changes that replace it at once like undo and edits
+   */
+  pub fn rehash(&mut self, playfield: &[u32]) {
+    let width = self.columns.len();
+    self.row_hashes = playfield.chunks_exact(width).map(|row| self.hash_row(row)).collect();
+    self.sum_rows();
+  }
+
+  /**
+   * A cell was filled or emptied
+   */
+  pub fn toggle(&mut self, x: usize, y: usize) {
+    self.board ^= self.row_term(y);
+    self.row_hashes[y] ^= self.columns[x];
+    self.board ^= self.row_term(y);
+  }
+
+  /**
+   * The rows moved down after a line clear, pairs of old and new row numbers of the rows that stayed
+   */
+  pub fn collapse(&mut self, copy_map: &[(usize, usize)]) {
+    let mut row_hashes = vec![0; self.row_hashes.len()];
+    for &(i, j) in copy_map {
+      row_hashes[j] = self.row_hashes[i];
+    }
+    self.row_hashes = row_hashes;
+    self.sum_rows();
+  }
+
+  /**
+   * The rows moved up and the row was added at the bottom
+   */
+  pub fn push_row(&mut self, row: &[u32]) {
+    self.row_hashes.remove(0);
+    self.row_hashes.push(self.hash_row(row));
+    self.sum_rows();
+  }
+
+  pub fn board(&self) -> u64 {
+    self.board
+  }
+
+  pub fn piece(&self, block: &Block) -> u64 {
+    let key = self.pieces[(block.block_type as usize - 1) * 4 + block.rot_state as usize];
+    mix(key ^ ((block.origin.0 as u64) << 32) ^ block.origin.1 as u32 as u64)
+  }
+
+  pub fn hold(&self, hold: Option<BlockType>) -> u64 {
+    self.hold[hold.map_or(0, |block_type| block_type as usize)]
+  }
+
+  pub fn next(&self, next: Option<BlockType>) -> u64 {
+    self.next[next.map_or(0, |block_type| block_type as usize)]
+  }
+
+  /**
+   * The piece, hold and next hashes of the second player, so swapping the players changes the hash
+   */
+  pub fn partner(&self, hash: u64) -> u64 {
+    mix(hash ^ self.partner)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{MoveDirection, Tetris, TetrisAction, TetrisBuilder};
+
+  fn rehashed(tetris: &Tetris) -> u64 {
+    let mut zobrist = Zobrist::new(tetris.dim.width, tetris.dim.height);
+    zobrist.rehash(&tetris.playfield.borrow());
+    zobrist.board()
+  }
+
+  fn act(tetris: &mut Tetris, action: TetrisAction) {
+    tetris.do_action(action);
+    while !tetris.phase().has_block() {
+      tetris.tick();
+    }
+  }
+
+  #[test]
+  fn follows_locks_and_line_clears() {
+    use TetrisAction::*;
+
+    let mut tetris = TetrisBuilder::new(0, 0)
+      .with_board("
+        xxxxxxxxxx
+        xxxxxxxxxx
+        xxxxxxxxxx
+        ZZZZxxZZZZ
+        ZZZZxxZZZZ
+      ".parse().unwrap())
+      .with_queue(vec![BlockType::O, BlockType::O, BlockType::T])
+      .build();
+    assert_ne!(tetris.zobrist.board(), 0);
+    assert_eq!(tetris.zobrist.board(), rehashed(&tetris));
+
+    // the position of the current block counts, not the way it got there
+    let spawn = tetris.zobrist();
+    tetris.do_action(Move(MoveDirection::Left));
+    assert_ne!(tetris.zobrist(), spawn);
+    tetris.do_action(Move(MoveDirection::Right));
+    assert_eq!(tetris.zobrist(), spawn);
+    assert_eq!(tetris.zobrist_with(&tetris.curr_block), spawn);
+
+    // clearing every row leaves the hash of an empty playfield
+    act(&mut tetris, HardDrop);
+    assert_eq!(tetris.lines(), 2);
+    assert_eq!(tetris.zobrist.board(), 0);
+
+    act(&mut tetris, Move(MoveDirection::Right));
+    act(&mut tetris, HardDrop);
+    assert_eq!(tetris.zobrist.board(), rehashed(&tetris));
+
+    // hold and the next block are part of the hash
+    let before = tetris.zobrist();
+    tetris.do_action(Hold);
+    assert_ne!(tetris.zobrist(), before);
+  }
+
+  #[test]
+  fn hashes_both_players_of_a_coop_game() {
+    use TetrisAction::*;
+
+    let mut tetris = TetrisBuilder::new(20, 8).with_coop().build();
+    let start = tetris.zobrist();
+    tetris.do_player_action(1, Move(MoveDirection::Left));
+    assert_ne!(tetris.zobrist(), start);
+    tetris.do_player_action(1, Move(MoveDirection::Right));
+    assert_eq!(tetris.zobrist(), start);
+    tetris.do_player_action(1, Hold);
+    assert_ne!(tetris.zobrist(), start);
+
+    // the same blocks in the hands of the other player are a different state
+    let swapped = tetris.as_player(1, |tetris| tetris.zobrist());
+    assert_ne!(swapped, tetris.zobrist());
+  }
+}
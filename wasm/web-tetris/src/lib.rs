@@ -5,7 +5,7 @@ use getrandom;
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{console, CanvasRenderingContext2d, OffscreenCanvas, Performance};
-use tetris::{Tetris, TetrisBuilder, Randomizer, MoveDirection, RotationDirection, TetrisAction};
+use tetris::{Tetris, TetrisBuilder, TetrisEvent, MoveDirection, RotationDirection, TetrisAction, CellKind, Replay, FRAMES_PER_SECOND};
 
 #[cfg(feature = "wee_alloc")]
 #[global_allocator]
@@ -17,9 +17,6 @@ pub fn run() {
     utils::set_panic_hook();
 }
 
-// TODO: make this customizable
-const PLAYFIELD_DIM: (usize, usize) = (10, 20); // width, height
-
 /**
  * Input Ids
  * 0 - Down
@@ -27,6 +24,8 @@ const PLAYFIELD_DIM: (usize, usize) = (10, 20); // width, height
  * 2 - Right
  * 3 - Rotate counteclockwise
  * 4 - Rotate clockwise
+ * 5 - Hold
+ * 6 - Hard drop
  */
 #[wasm_bindgen]
 pub struct WebTetris {
@@ -35,7 +34,21 @@ pub struct WebTetris {
   canvas: OffscreenCanvas,
   square_drawer: SquareDrawer,
   tetris: Tetris, // tetris logic and state
-  last_update_time: f64,
+  last_tick_time: f64, // gravity and delays follow the game clock, see `Tetris::tick`
+  replay: Replay, // inputs of the current game
+  last_submission: Option<String>, // score and replay of the last game that ended, for the leaderboard
+}
+
+fn random_seed() -> u64 {
+  let mut buf = [0u8; 8];
+  getrandom::getrandom(&mut buf).unwrap();
+  u64::from_le_bytes(buf)
+}
+
+// every game gets a new seed, so it can be played again from its replay
+fn new_game() -> (Tetris, Replay) {
+  let seed = random_seed();
+  (TetrisBuilder::leaderboard().with_seed(seed).build(), Replay::new(seed))
 }
 
 #[wasm_bindgen]
@@ -52,8 +65,10 @@ impl WebTetris {
 
     canvas.set_width(canvas.height() / 2);
 
+    let (tetris, replay) = new_game();
+
     let square_drawer = {
-      let square_length = canvas.height() as f64 / PLAYFIELD_DIM.1 as f64;
+      let square_length = canvas.height() as f64 / tetris.dim.height as f64;
       let square_padding = (square_length * 0.1_f64).sqrt();
       SquareDrawer::new(square_length, square_padding)
     };
@@ -64,27 +79,7 @@ impl WebTetris {
       .dyn_into::<web_sys::Performance>()
       .unwrap();
 
-    let last_update_time = timer.now();
-
-    let randomizer: Box<dyn Randomizer<u32>> = {
-      
-      struct WebRandomizer;
-      impl Randomizer<u32> for WebRandomizer {
-          fn get_random(&mut self) -> u32 {
-            let mut buf = [0u8; 1];
-            getrandom::getrandom(&mut buf).unwrap();
-            buf[0] as u32
-          }
-      }
-
-      Box::new(WebRandomizer {})
-    };
-
-    let tetris = TetrisBuilder {
-      width: PLAYFIELD_DIM.0,
-      height: PLAYFIELD_DIM.1,
-      randomizer,
-    }.build();
+    let last_tick_time = timer.now();
 
     Self {
       timer,
@@ -92,30 +87,26 @@ impl WebTetris {
       canvas,
       square_drawer,
       tetris,
-      last_update_time,
+      last_tick_time,
+      replay,
+      last_submission: None,
     }
   }
 
   pub fn render(&self) {
-
-    // draw object
-    for col in self.tetris.curr_block.pos.column_iter() {
+    let frame = self.tetris.frame();
+
+    for (i, cell) in frame.cells.iter().enumerate() {
+      let color = match cell.kind {
+        CellKind::Empty => continue,
+        CellKind::Ghost => "lightgray",
+        // lines being cleared are grayed out until they disappear
+        CellKind::Locked if frame.clearing.contains(&(i / frame.width)) => "gray",
+        CellKind::Locked | CellKind::Active => Self::match_color(cell.value),
+      };
       self
         .square_drawer
-        .draw(&self.ctx, (col[(0, 0)] as f64, col[(1, 0)] as f64), Self::match_color(self.tetris.curr_block.block_type as u32));
-    }
-
-    // draw playfield
-    for (i, p) in self.tetris.playfield.borrow().iter().enumerate() {
-      if *p > 0 {
-        self
-          .square_drawer
-          .draw(
-            &self.ctx,
-            ((i % PLAYFIELD_DIM.0) as f64, (i as f64 / PLAYFIELD_DIM.0 as f64).floor()),
-            Self::match_color(*p),
-          );
-      }
+        .draw(&self.ctx, ((i % frame.width) as f64, (i / frame.width) as f64), color);
     }
   }
 
@@ -143,9 +134,26 @@ impl WebTetris {
 
   pub fn update(&mut self) {
     let now = self.timer.now();
-    if now > self.last_update_time + 1000_f64 {
-      self.last_update_time = now;
-      self.tetris.update();
+
+    // advance the game clock by the frames that passed since the last update
+    let frame_time = 1000_f64 / FRAMES_PER_SECOND as f64;
+    while now > self.last_tick_time + frame_time {
+      self.last_tick_time += frame_time;
+      self.tetris.tick();
+    }
+
+    for event in self.tetris.drain_events() {
+      if let TetrisEvent::GameOver(stats) = event {
+        console::log_1(&format!(
+          "[Tetris] Game over, {} pieces, {} lines, {:.2} pps, {:.2} kpp, {:.1} apm",
+          stats.pieces, stats.lines, stats.pps(), stats.kpp(), stats.apm()
+        ).into());
+        self.replay.finish(&self.tetris);
+        self.last_submission = Some(format!("score {}\n{}", self.tetris.score(), self.replay));
+        let (tetris, replay) = new_game();
+        self.tetris = tetris;
+        self.replay = replay;
+      }
     }
   }
 
@@ -165,22 +173,33 @@ impl WebTetris {
       2 => Some(Move(Right)),
       3 => Some(Rotate(CounterClockwise)),
       4 => Some(Rotate(Clockwise)),
+      5 => Some(Hold),
+      6 => Some(HardDrop),
       _ => None,
     };
 
     if let Some(action) = action {
+      self.replay.record(&self.tetris, action);
       self.tetris.do_action(action);
     };
 
   }
 
+  /**
+   * Score and replay of the last game that ended, in the format the leaderboard verifies with `tetris-verify`
+   */
+  #[wasm_bindgen(js_name = lastSubmission)]
+  pub fn last_submission(&self) -> Option<String> {
+    self.last_submission.clone()
+  }
+
   // TODO rename to set_size
   pub fn resize(&mut self, width: u32, height: u32) {
 
     self.canvas.set_width(width);
     self.canvas.set_height(height);
     self.square_drawer = {
-      let square_length = self.canvas.height() as f64 / PLAYFIELD_DIM.1 as f64;
+      let square_length = self.canvas.height() as f64 / self.tetris.dim.height as f64;
       let square_padding = (square_length * 0.1_f64).sqrt();
       SquareDrawer::new(square_length, square_padding)
     };